@@ -0,0 +1,380 @@
+// 自定义帧格式解析：帧头 + 长度字段 + 负载 + 可选 CRC16
+use serde::{Deserialize, Serialize};
+
+// 单帧最大负载长度，超过视为失步
+const MAX_PAYLOAD_LEN: usize = 4096;
+
+// 帧格式定义
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameSpec {
+    pub header_byte: u8,
+    // 长度字段相对帧头的偏移（帧头位于 0）
+    pub length_field_offset: usize,
+    // 长度字段字节数（1~4，多字节按大端解析），值为负载长度
+    pub length_field_size: usize,
+    // 帧尾是否带 CRC16（Modbus，低字节在前），校验范围为帧头到负载末尾
+    pub has_crc16: bool,
+}
+
+impl FrameSpec {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=4).contains(&self.length_field_size) {
+            return Err("length_field_size must be between 1 and 4".to_string());
+        }
+        if self.length_field_offset == 0 {
+            return Err("length_field_offset must be after the header byte".to_string());
+        }
+        Ok(())
+    }
+}
+
+// 解析结果：完整帧或需要单独上报的错误/失步字节
+#[derive(Debug, Clone)]
+pub enum FrameOutput {
+    Frame { payload: Vec<u8>, raw: Vec<u8> },
+    Error { reason: String, bytes: Vec<u8> },
+}
+
+// 带累积缓冲的帧解析器
+#[derive(Debug, Clone)]
+pub struct FrameParser {
+    pub spec: FrameSpec,
+    buffer: Vec<u8>,
+}
+
+impl FrameParser {
+    pub fn new(spec: FrameSpec) -> Self {
+        FrameParser { spec, buffer: Vec::new() }
+    }
+
+    // 追加新数据并取出所有完整帧
+    pub fn feed(&mut self, data: &[u8]) -> Vec<FrameOutput> {
+        self.buffer.extend_from_slice(data);
+        let mut outputs = Vec::new();
+
+        loop {
+            // 丢弃帧头之前的字节
+            match self.buffer.iter().position(|&b| b == self.spec.header_byte) {
+                Some(0) => {}
+                Some(pos) => {
+                    let skipped: Vec<u8> = self.buffer.drain(..pos).collect();
+                    outputs.push(FrameOutput::Error {
+                        reason: "desync".to_string(),
+                        bytes: skipped,
+                    });
+                }
+                None => {
+                    if !self.buffer.is_empty() {
+                        outputs.push(FrameOutput::Error {
+                            reason: "desync".to_string(),
+                            bytes: std::mem::take(&mut self.buffer),
+                        });
+                    }
+                    break;
+                }
+            }
+
+            let length_end = self.spec.length_field_offset + self.spec.length_field_size;
+            if self.buffer.len() < length_end {
+                break;
+            }
+
+            let payload_len = self.buffer[self.spec.length_field_offset..length_end]
+                .iter()
+                .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+            if payload_len > MAX_PAYLOAD_LEN {
+                // 长度异常，丢弃当前帧头重新同步
+                let bad: Vec<u8> = self.buffer.drain(..1).collect();
+                outputs.push(FrameOutput::Error {
+                    reason: format!("invalid length {}", payload_len),
+                    bytes: bad,
+                });
+                continue;
+            }
+
+            let payload_end = length_end + payload_len;
+            let frame_len = payload_end + if self.spec.has_crc16 { 2 } else { 0 };
+            if self.buffer.len() < frame_len {
+                break;
+            }
+
+            if self.spec.has_crc16 {
+                let expected = crc16_modbus(&self.buffer[..payload_end]);
+                let actual = u16::from_le_bytes([
+                    self.buffer[payload_end],
+                    self.buffer[payload_end + 1],
+                ]);
+                if expected != actual {
+                    // 校验失败时只丢弃帧头，帧内可能包含下一帧的起点
+                    outputs.push(FrameOutput::Error {
+                        reason: format!(
+                            "crc mismatch: expected {:04X}, got {:04X}",
+                            expected, actual
+                        ),
+                        bytes: self.buffer[..frame_len].to_vec(),
+                    });
+                    self.buffer.drain(..1);
+                    continue;
+                }
+            }
+
+            let raw: Vec<u8> = self.buffer.drain(..frame_len).collect();
+            outputs.push(FrameOutput::Frame {
+                payload: raw[length_end..payload_end].to_vec(),
+                raw,
+            });
+        }
+
+        outputs
+    }
+}
+
+// CRC16/Modbus（多项式 0xA001，初值 0xFFFF）
+pub fn crc16_modbus(data: &[u8]) -> u16 {
+    crc16_modbus_update(0xFFFF, data)
+}
+
+// 在已有 CRC 值上继续累加数据
+fn crc16_modbus_update(mut crc: u16, data: &[u8]) -> u16 {
+    for &byte in data {
+        crc ^= byte as u16;
+        for _ in 0..8 {
+            if crc & 1 != 0 {
+                crc = (crc >> 1) ^ 0xA001;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+    crc
+}
+
+// 和校验（累加和取低 8 位）
+pub fn sum8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |acc, &b| acc.wrapping_add(b))
+}
+
+// 异或校验
+pub fn xor8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |acc, &b| acc ^ b)
+}
+
+// 按校验类型计算校验字节：none / sum8 / xor8 / crc16_modbus（低字节在前）
+pub fn checksum_bytes(kind: &str, data: &[u8]) -> Result<Vec<u8>, String> {
+    match kind.to_ascii_lowercase().as_str() {
+        "none" => Ok(vec![]),
+        "sum8" => Ok(vec![sum8(data)]),
+        "xor8" => Ok(vec![xor8(data)]),
+        "crc16_modbus" | "crc16" => Ok(crc16_modbus(data).to_le_bytes().to_vec()),
+        other => Err(format!("Unsupported checksum type: {}", other)),
+    }
+}
+
+// 增量校验状态，用于边接收边计算，不需要缓存整段数据
+#[derive(Debug, Clone)]
+pub enum RunningChecksum {
+    Sum8(u8),
+    Xor8(u8),
+    Crc16Modbus(u16),
+}
+
+impl RunningChecksum {
+    pub fn new(kind: &str) -> Result<Self, String> {
+        match kind.to_ascii_lowercase().as_str() {
+            "sum8" => Ok(RunningChecksum::Sum8(0)),
+            "xor8" => Ok(RunningChecksum::Xor8(0)),
+            "crc16_modbus" | "crc16" => Ok(RunningChecksum::Crc16Modbus(0xFFFF)),
+            other => Err(format!("Unsupported checksum type: {}", other)),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            RunningChecksum::Sum8(acc) => *acc = acc.wrapping_add(sum8(data)),
+            RunningChecksum::Xor8(acc) => *acc ^= xor8(data),
+            RunningChecksum::Crc16Modbus(crc) => *crc = crc16_modbus_update(*crc, data),
+        }
+    }
+
+    // 结果字节序与 checksum_bytes 一致
+    pub fn finalize(&self) -> Vec<u8> {
+        match self {
+            RunningChecksum::Sum8(acc) | RunningChecksum::Xor8(acc) => vec![*acc],
+            RunningChecksum::Crc16Modbus(crc) => crc.to_le_bytes().to_vec(),
+        }
+    }
+}
+
+// 固定格式帧的校验规则，未设置的字段不检查
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FrameSchema {
+    pub start_byte: Option<u8>,
+    // 整帧长度（包括起始字节和校验）
+    pub length: Option<usize>,
+    // 帧尾校验类型，校验范围为帧首到校验字段之前
+    pub checksum: Option<String>,
+}
+
+// 按规则校验一帧，失败时返回原因
+pub fn validate_frame(bytes: &[u8], schema: &FrameSchema) -> Result<(), String> {
+    if bytes.is_empty() {
+        return Err("empty frame".to_string());
+    }
+    if let Some(start_byte) = schema.start_byte {
+        if bytes[0] != start_byte {
+            return Err(format!(
+                "start byte mismatch: expected {:02X}, got {:02X}",
+                start_byte, bytes[0]
+            ));
+        }
+    }
+    if let Some(length) = schema.length {
+        if bytes.len() != length {
+            return Err(format!(
+                "length mismatch: expected {}, got {}",
+                length,
+                bytes.len()
+            ));
+        }
+    }
+    if let Some(kind) = &schema.checksum {
+        let width = checksum_bytes(kind, &[])?.len();
+        if bytes.len() < width {
+            return Err(format!("frame too short for {} checksum", kind));
+        }
+        let (body, actual) = bytes.split_at(bytes.len() - width);
+        let expected = checksum_bytes(kind, body)?;
+        if actual != expected.as_slice() {
+            return Err(format!(
+                "checksum mismatch: expected {}, got {}",
+                hex_bytes(&expected),
+                hex_bytes(actual)
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(length_field_size: usize, has_crc16: bool) -> FrameSpec {
+        FrameSpec { header_byte: 0xAA, length_field_offset: 1, length_field_size, has_crc16 }
+    }
+
+    // 构造一帧：帧头 + 1 字节长度 + 负载 + CRC16
+    fn build_frame(payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0xAA, payload.len() as u8];
+        frame.extend_from_slice(payload);
+        let crc = crc16_modbus(&frame);
+        frame.extend_from_slice(&crc.to_le_bytes());
+        frame
+    }
+
+    fn frames(outputs: &[FrameOutput]) -> Vec<Vec<u8>> {
+        outputs
+            .iter()
+            .filter_map(|o| match o {
+                FrameOutput::Frame { payload, .. } => Some(payload.clone()),
+                FrameOutput::Error { .. } => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn crc16_modbus_check_value() {
+        assert_eq!(crc16_modbus(b"123456789"), 0x4B37);
+    }
+
+    #[test]
+    fn running_checksum_matches_one_shot() {
+        let data = b"split across several reads";
+        for kind in ["sum8", "xor8", "crc16_modbus"] {
+            let mut running = RunningChecksum::new(kind).unwrap();
+            for chunk in data.chunks(5) {
+                running.update(chunk);
+            }
+            assert_eq!(running.finalize(), checksum_bytes(kind, data).unwrap());
+        }
+    }
+
+    #[test]
+    fn frame_split_across_reads() {
+        let frame = build_frame(&[1, 2, 3, 4]);
+        let mut parser = FrameParser::new(spec(1, true));
+        assert!(parser.feed(&frame[..3]).is_empty());
+        assert!(parser.feed(&frame[3..6]).is_empty());
+        let outputs = parser.feed(&frame[6..]);
+        assert_eq!(frames(&outputs), vec![vec![1, 2, 3, 4]]);
+    }
+
+    #[test]
+    fn resyncs_after_garbage() {
+        let mut data = vec![0x01, 0x02, 0x03];
+        data.extend(build_frame(&[9, 9]));
+        let mut parser = FrameParser::new(spec(1, true));
+        let outputs = parser.feed(&data);
+        assert!(matches!(
+            &outputs[0],
+            FrameOutput::Error { reason, bytes } if reason == "desync" && bytes == &[1, 2, 3]
+        ));
+        assert_eq!(frames(&outputs), vec![vec![9, 9]]);
+    }
+
+    #[test]
+    fn bad_crc_is_reported_and_next_frame_parsed() {
+        let mut bad = build_frame(&[5, 6]);
+        let last = bad.len() - 1;
+        bad[last] ^= 0xFF;
+        let mut data = bad.clone();
+        data.extend(build_frame(&[7]));
+
+        let mut parser = FrameParser::new(spec(1, true));
+        let outputs = parser.feed(&data);
+        assert!(matches!(
+            &outputs[0],
+            FrameOutput::Error { reason, bytes } if reason.starts_with("crc mismatch") && bytes == &bad
+        ));
+        assert_eq!(frames(&outputs), vec![vec![7]]);
+    }
+
+    #[test]
+    fn length_field_at_limit() {
+        let mut parser = FrameParser::new(spec(2, false));
+        let mut frame = vec![0xAA];
+        frame.extend_from_slice(&(MAX_PAYLOAD_LEN as u16).to_be_bytes());
+        frame.extend(vec![0x55; MAX_PAYLOAD_LEN]);
+        let outputs = parser.feed(&frame);
+        assert_eq!(frames(&outputs), vec![vec![0x55; MAX_PAYLOAD_LEN]]);
+
+        let mut parser = FrameParser::new(spec(2, false));
+        let mut too_long = vec![0xAA];
+        too_long.extend_from_slice(&(MAX_PAYLOAD_LEN as u16 + 1).to_be_bytes());
+        let outputs = parser.feed(&too_long);
+        assert!(matches!(
+            &outputs[0],
+            FrameOutput::Error { reason, .. } if reason == &format!("invalid length {}", MAX_PAYLOAD_LEN + 1)
+        ));
+        assert!(frames(&outputs).is_empty());
+    }
+
+    #[test]
+    fn validate_frame_checks_each_rule() {
+        let schema = FrameSchema {
+            start_byte: Some(0x7E),
+            length: Some(4),
+            checksum: Some("sum8".to_string()),
+        };
+        assert!(validate_frame(&[0x7E, 0x01, 0x02, 0x81], &schema).is_ok());
+        assert!(validate_frame(&[0x7F, 0x01, 0x02, 0x82], &schema).unwrap_err().starts_with("start byte"));
+        assert!(validate_frame(&[0x7E, 0x01, 0x81], &schema).unwrap_err().starts_with("length"));
+        assert!(validate_frame(&[0x7E, 0x01, 0x02, 0x00], &schema).unwrap_err().starts_with("checksum"));
+        assert!(validate_frame(&[], &schema).is_err());
+    }
+}
@@ -1,23 +1,312 @@
 use serde::{Deserialize, Serialize};
 use serialport::{SerialPort, SerialPortType};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
+use tauri::Emitter;
+
+mod frame;
+mod tcp_port;
+mod ymodem;
+use frame::{FrameOutput, FrameParser, FrameSchema, FrameSpec, RunningChecksum};
+use tcp_port::{TcpSerialPort, TCP_PREFIX};
 
 // 全局串口连接管理器
-static SERIAL_PORTS: Lazy<Arc<Mutex<HashMap<String, Box<dyn SerialPort>>>>> = 
+static SERIAL_PORTS: Lazy<Arc<Mutex<HashMap<String, PortHandles>>>> = 
     Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
 
 // 虚拟串口数据缓冲（简化实现，移除未使用的 channel）
 static VIRTUAL_BUFFERS: Lazy<Arc<Mutex<HashMap<String, Vec<u8>>>>> = 
     Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
 
+// 虚拟串口配置（自动应答表等），与缓冲区分开保存，关闭端口后仍保留
+static VIRTUAL_CONFIGS: Lazy<Arc<Mutex<HashMap<String, VirtualPortConfig>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 终端行编辑缓冲：port_name -> 当前输入行
+static TERMINAL_LINES: Lazy<Arc<Mutex<HashMap<String, String>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 后台监听线程：port_name -> 监听句柄
+static LISTENERS: Lazy<Arc<Mutex<HashMap<String, ListenerHandle>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 监听线程使用的帧解析器：port_name -> 解析器
+static FRAME_PARSERS: Lazy<Arc<Mutex<HashMap<String, FrameParser>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 真实串口的可复用读缓冲：port_name -> 缓冲区
+static READ_SCRATCH: Lazy<Arc<Mutex<HashMap<String, Vec<u8>>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 真实串口当前生效的读超时（毫秒）：port_name -> 超时，serialport 的 getter 需要锁住读句柄
+static READ_TIMEOUTS: Lazy<Arc<Mutex<HashMap<String, u64>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 接收数据的增量校验：port_name -> 校验状态
+static STREAM_CHECKSUMS: Lazy<Arc<Mutex<HashMap<String, RunningChecksum>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 测试注入的待读取数据：port_name -> 字节，read 时先于硬件数据返回
+#[cfg(feature = "test-inject")]
+static INJECTED_READS: Lazy<Arc<Mutex<HashMap<String, Vec<u8>>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 通过 BOM 识别出的文本编码：port_name -> 编码，之后的读取沿用直到端口关闭
+static DETECTED_ENCODINGS: Lazy<Arc<Mutex<HashMap<String, &'static encoding_rs::Encoding>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 已暂停 I/O 的端口：句柄和配置保持不变，读写返回 Paused 错误，监听线程空转等待
+static PAUSED_PORTS: Lazy<Arc<Mutex<std::collections::HashSet<String>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(std::collections::HashSet::new())));
+
+// 各端口最近一次读写错误；关闭端口后仍保留，下一次成功读写时清除
+static LAST_ERRORS: Lazy<Arc<Mutex<HashMap<String, LastError>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 状态线变化记录线程的停止标志：port_name -> stop
+static SIGNAL_LOGS: Lazy<Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 自动关闭监视线程的停止标志：port_name -> stop
+static AUTO_CLOSE: Lazy<Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// read_samples 未凑满一个采样的剩余字节：port_name -> (采样类型, 剩余字节)
+static SAMPLE_REMAINDERS: Lazy<Arc<Mutex<HashMap<String, (String, Vec<u8>)>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 单次读取的缓冲大小
+const READ_CHUNK_SIZE: usize = 1024;
+
+// 打开端口时设置的初始读超时（毫秒）
+const OPEN_READ_TIMEOUT_MS: u64 = 100;
+
+// 按波特率推荐读缓冲时的上下限
+const MIN_READ_CHUNK_SIZE: usize = 64;
+const MAX_READ_CHUNK_SIZE: usize = 16384;
+
+// 读取遇到 Interrupted / WouldBlock 时的最大重试次数
+const READ_RETRY_LIMIT: u32 = 3;
+
+// 端口统计：port_name -> 统计数据
+static PORT_STATS: Lazy<Arc<Mutex<HashMap<String, PortStats>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 虚拟串口 stream 模式的后台线程：port_name -> 线程句柄
+static VIRTUAL_STREAMS: Lazy<Arc<Mutex<HashMap<String, ListenerHandle>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 收发数据捕获（环形缓冲）：port_name -> 按时间排序的收发记录，关闭端口后保留以便导出
+static CAPTURES: Lazy<Arc<Mutex<HashMap<String, std::collections::VecDeque<CaptureEntry>>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 每个端口最多保留的捕获记录条数
+const MAX_CAPTURE_ENTRIES: usize = 10000;
+
+// 最近一次成功打开时使用的配置：port_name -> 配置，供自动重连使用
+static PORT_CONFIGS: Lazy<Arc<Mutex<HashMap<String, SerialConfig>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 自动重连策略：port_name -> 策略
+static AUTO_RECONNECT: Lazy<Arc<Mutex<HashMap<String, ReconnectPolicy>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 后端运行日志（环形缓冲），供界面拉取以便远程排查
+static RECENT_LOGS: Lazy<Arc<Mutex<std::collections::VecDeque<String>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(std::collections::VecDeque::new())));
+
+// 最多保留的日志行数
+const MAX_LOG_LINES: usize = 1000;
+
+// 当前日志级别，数值越大越详细（默认 debug，保持原有输出）
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Debug as u8);
+
+// Unix 下记录真实串口的文件描述符，用于 termios 操作
+#[cfg(unix)]
+static PORT_FDS: Lazy<Arc<Mutex<HashMap<String, std::os::unix::io::RawFd>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// PTY 从设备端句柄：handle_id -> 从设备端口；保持打开，避免外部程序未连接时主设备端读取报错
+#[cfg(unix)]
+static PTY_SLAVES: Lazy<Arc<Mutex<HashMap<String, serialport::TTYPort>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// PTY 句柄编号计数器
+static PTY_COUNTER: AtomicU64 = AtomicU64::new(1);
+
+// 串口会话归属：port_name -> session_id
+static PORT_SESSIONS: Lazy<Arc<Mutex<HashMap<String, String>>>> = 
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// 会话 ID 计数器
+static SESSION_COUNTER: AtomicU64 = AtomicU64::new(1);
+
+// 读取命令未指定超时（或为 0）时使用的默认超时（毫秒）
+static DEFAULT_READ_TIMEOUT_MS: AtomicU64 = AtomicU64::new(1000);
+
+// 是否在端口列表中显示未打开的演示虚拟串口
+static VIRTUAL_PORTS_VISIBLE: AtomicBool = AtomicBool::new(true);
+
+// 日志级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum LogLevel {
+    Error = 0,
+    Warning = 1,
+    Info = 2,
+    Debug = 3,
+}
+
+impl LogLevel {
+    fn parse(level: &str) -> Result<Self, String> {
+        match level.to_ascii_lowercase().as_str() {
+            "error" => Ok(LogLevel::Error),
+            "warning" | "warn" => Ok(LogLevel::Warning),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            other => Err(format!("Unsupported log level: {}", other)),
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => LogLevel::Error,
+            1 => LogLevel::Warning,
+            2 => LogLevel::Info,
+            _ => LogLevel::Debug,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            LogLevel::Error => "ERROR",
+            LogLevel::Warning => "WARNING",
+            LogLevel::Info => "INFO",
+            LogLevel::Debug => "DEBUG",
+        }
+    }
+}
+
+// 当前时间（Unix 毫秒）
+fn unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// 输出日志到 stdout 并写入环形缓冲，低于当前级别的日志直接丢弃
+fn log_message(level: LogLevel, message: String) {
+    if level as u8 > LOG_LEVEL.load(Ordering::Relaxed) {
+        return;
+    }
+    println!("[{}] {}", level.label(), message);
+
+    let mut logs = RECENT_LOGS.lock()
+        .expect("Failed to lock RECENT_LOGS mutex");
+    if logs.len() >= MAX_LOG_LINES {
+        logs.pop_front();
+    }
+    logs.push_back(format!("{} [{}] {}", unix_millis(), level.label(), message));
+}
+
+// 设置日志级别：error | warning | info | debug
+#[tauri::command]
+fn set_log_level(level: String) -> Result<String, String> {
+    let level = LogLevel::parse(&level)?;
+    LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+    Ok(format!("Log level set to {}", level.label()))
+}
+
+// 获取最近的日志（按时间顺序，最多 max_lines 行）
+#[tauri::command]
+fn get_recent_logs(max_lines: usize) -> Vec<String> {
+    let logs = RECENT_LOGS.lock()
+        .expect("Failed to lock RECENT_LOGS mutex");
+    let skip = logs.len().saturating_sub(max_lines);
+    logs.iter().skip(skip).cloned().collect()
+}
+
+// 加锁共享的串口句柄
+type SharedPort = Arc<Mutex<Box<dyn SerialPort>>>;
+
+// 真实串口句柄：通过 try_clone 分离读写句柄并各自加锁，阻塞读取不会卡住写入
+// 设置参数、清缓冲、控制线等操作统一使用写句柄
+struct PortHandles {
+    reader: SharedPort,
+    writer: SharedPort,
+}
+
+impl PortHandles {
+    // try_clone 在个别驱动/平台上可能失败（例如部分 Windows 虚拟串口驱动不支持
+    // DuplicateHandle），此时读写共用同一句柄，行为退化为串行访问
+    fn new(port_name: &str, port: Box<dyn SerialPort>) -> Self {
+        record_read_timeout(port_name, port.timeout());
+        let cloned = port.try_clone();
+        let reader: SharedPort = Arc::new(Mutex::new(port));
+        let writer = match cloned {
+            Ok(clone) => Arc::new(Mutex::new(clone)),
+            Err(e) => {
+                log_message(LogLevel::Warning, format!("串口 {} 不支持 try_clone，读写将共用句柄: {}", port_name, e));
+                reader.clone()
+            }
+        };
+        PortHandles { reader, writer }
+    }
+}
+
+// 端口已暂停时返回 Paused 错误
+fn check_not_paused(port_name: &str) -> Result<(), String> {
+    if is_port_paused(port_name) {
+        return Err(format!("Paused: port {} is paused, call resume_port first", port_name));
+    }
+    Ok(())
+}
+
+fn is_port_paused(port_name: &str) -> bool {
+    PAUSED_PORTS.lock()
+        .expect("Failed to lock PAUSED_PORTS mutex")
+        .contains(port_name)
+}
+
+// 获取真实串口的读句柄（不持有全局锁）
+fn port_reader(port_name: &str) -> Result<SharedPort, String> {
+    check_not_paused(port_name)?;
+    let ports = SERIAL_PORTS.lock()
+        .expect("Failed to lock SERIAL_PORTS mutex");
+    ports.get(port_name)
+        .map(|handles| handles.reader.clone())
+        .ok_or_else(|| format!("Port {} not found", port_name))
+}
+
+// 获取真实串口的写/控制句柄（不持有全局锁）
+fn port_writer(port_name: &str) -> Result<SharedPort, String> {
+    check_not_paused(port_name)?;
+    let ports = SERIAL_PORTS.lock()
+        .expect("Failed to lock SERIAL_PORTS mutex");
+    ports.get(port_name)
+        .map(|handles| handles.writer.clone())
+        .ok_or_else(|| format!("Port {} not found", port_name))
+}
+
 // 串口信息结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct SerialPortInfo {
     port_name: String,
     port_type: String,
+    // 端口在本应用中的状态："free" | "open_here"
+    state: String,
+}
+
+// 串口列表结果：枚举物理串口失败时仍返回虚拟串口，并通过 warning 说明原因
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SerialPortList {
+    ports: Vec<SerialPortInfo>,
+    warning: Option<String>,
+    // 当前平台是否支持物理串口；为 false 时界面应进入仅虚拟串口模式
+    hardware_supported: bool,
 }
 
 // 串口配置结构
@@ -28,19 +317,296 @@ struct SerialConfig {
     data_bits: u8,
     stop_bits: u8,
     parity: String,
+    // 打开失败（端口忙/拒绝访问）时的重试次数与间隔
+    retry_count: Option<u32>,
+    retry_delay_ms: Option<u64>,
+    // 打开后等待芯片稳定的时间（CH340/CP2102 等），默认 0
+    settle_ms: Option<u64>,
+    // 稳定期间是否拉低 DTR
+    settle_dtr_low: Option<bool>,
+    // 打开时是否拉高 DTR/RTS（默认 true）；false 时保持线路不动作，避免 Arduino 类开发板复位
+    assert_lines_on_open: Option<bool>,
+    // 高级打开选项，可一次设置全部；与上面同名的顶层字段同时设置时以这里为准
+    advanced: Option<AdvancedOptions>,
+}
+
+// 打开串口的高级选项，含义与 SerialConfig 顶层同名字段一致，未设置的项取默认值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct AdvancedOptions {
+    retry_count: Option<u32>,
+    retry_delay_ms: Option<u64>,
+    settle_ms: Option<u64>,
+    settle_dtr_low: Option<bool>,
+    assert_lines_on_open: Option<bool>,
+}
+
+// 合并顶层字段与 advanced 后实际生效的打开选项
+struct OpenOptions {
+    retry_count: u32,
+    retry_delay: Duration,
+    settle: Duration,
+    settle_dtr_low: bool,
+    assert_lines: bool,
+}
+
+impl SerialConfig {
+    // advanced 优先，其次顶层字段，最后为默认值
+    fn open_options(&self) -> OpenOptions {
+        let advanced = self.advanced.clone().unwrap_or_default();
+        OpenOptions {
+            retry_count: advanced.retry_count.or(self.retry_count).unwrap_or(0),
+            retry_delay: Duration::from_millis(
+                advanced.retry_delay_ms.or(self.retry_delay_ms).unwrap_or(500),
+            ),
+            settle: Duration::from_millis(advanced.settle_ms.or(self.settle_ms).unwrap_or(0)),
+            settle_dtr_low: advanced.settle_dtr_low.or(self.settle_dtr_low).unwrap_or(false),
+            assert_lines: advanced.assert_lines_on_open.or(self.assert_lines_on_open).unwrap_or(true),
+        }
+    }
+}
+
+// 虚拟串口自动应答项：收到 request 时回复 response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VirtualResponse {
+    request: Vec<u8>,
+    response: Vec<u8>,
+}
+
+// 虚拟串口配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct VirtualPortConfig {
+    // 工作模式（echo / reply / random / stream / none，或 echo_upper 等变换回显），未设置时按端口名取默认值
+    mode: Option<String>,
+    responses: Vec<VirtualResponse>,
+    // stream 模式下定时推送的数据和间隔
+    stream_payload: Vec<u8>,
+    stream_interval_ms: Option<u64>,
+    // 模拟故障：disconnect_on_next_read / corrupt_1_percent / drop_writes / timeout_reads
+    fault: Option<String>,
+}
+
+// 后台读取线程的主函数，保存下来以便按相同参数重启
+type ListenerTask = Arc<dyn Fn(String, Arc<AtomicBool>) + Send + Sync>;
+
+// 后台监听线程句柄
+struct ListenerHandle {
+    stop: Arc<AtomicBool>,
+    thread: std::thread::JoinHandle<()>,
+    // 虚拟串口推送线程不支持重启，为 None
+    task: Option<ListenerTask>,
+}
+
+// 监听线程推送的数据事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SerialDataEvent {
+    port_name: String,
+    data: Vec<u8>,
+}
+
+// 监听线程出错时推送的事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SerialErrorEvent {
+    port_name: String,
+    error: String,
+}
+
+// 自动重连策略
+#[derive(Debug, Clone, Copy)]
+struct ReconnectPolicy {
+    max_attempts: u32,
+    delay_ms: u64,
+}
+
+// 自动重连过程中推送的事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SerialReconnectEvent {
+    port_name: String,
+    attempt: u32,
+    max_attempts: u32,
+}
+
+// 端口因空闲或超过最长时长被自动关闭时推送的事件，reason 为 idle / max_duration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SerialAutoClosedEvent {
+    port_name: String,
+    reason: String,
+    elapsed_ms: u64,
+}
+
+// 发送选项（均为可选，未传入时保持原有行为）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct WriteOptions {
+    // 写入后等待输出缓冲清空
+    confirm_drain: bool,
+    // 等待清空的超时，默认 1000ms
+    drain_timeout_ms: Option<u64>,
+    // HEX 模式下的字节序调整：none / swap16 / swap32
+    byte_order: Option<String>,
+    // 追加的行尾：none / cr / lf / crlf
+    line_ending: Option<String>,
+    // 文本模式下解析 \r \n \t \\ \0 \xHH 转义
+    parse_escapes: bool,
+    // 文本编码（utf-8、gbk、utf-16le 等），默认 utf-8
+    encoding: Option<String>,
+    // 写入超时：设备停止接收（如硬件流控 CTS 无效）时超时报错，默认沿用原有行为
+    write_timeout_ms: Option<u64>,
+    // 按分隔符拆分为多条命令分别发送，每条都追加行尾
+    split_on: Option<String>,
+    // 拆分发送时相邻两条之间的间隔
+    inter_frame_delay_ms: Option<u64>,
+    // 自动在 offset 处插入长度字段
+    length_field: Option<LengthField>,
+    // 写入失败时的恢复策略：abort（默认，直接报错）/ flush_and_retry（清空收发缓冲后重试一次）/
+    // reset_port（按原配置重新打开端口后重试一次）。重试会重发整帧，失败前已发出的部分可能重复
+    on_write_error: Option<String>,
+}
+
+// 自动插入的长度字段：值为字段之后的负载字节数（includes_self 时再加上字段本身）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LengthField {
+    offset: usize,
+    // 字段字节数：1 / 2 / 4
+    size: usize,
+    #[serde(default)]
+    includes_self: bool,
+    // be / le，默认 be
+    endian: Option<String>,
+}
+
+// 帧解析出完整帧时推送的事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SerialFrameEvent {
+    port_name: String,
+    payload: Vec<u8>,
+    raw: Vec<u8>,
+}
+
+// 帧解析遇到失步/校验失败时推送的事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SerialFrameErrorEvent {
+    port_name: String,
+    reason: String,
+    bytes: Vec<u8>,
+}
+
+// 端口最近一次读写错误
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LastError {
+    message: String,
+    timestamp_ms: u64,
+}
+
+// 端口运行统计（内部记录）
+struct PortStats {
+    opened_at: std::time::Instant,
+    open_duration_ms: u64,
+    first_byte_ms: Option<u64>,
+    bytes_sent: u64,
+    bytes_received: u64,
+    // 最近一次读写的时间，用于空闲自动关闭
+    last_io: std::time::Instant,
+    reconnects: u32,
+    // bitbang_write 在控制线上输出的位数，不计入字节统计和捕获
+    bitbang_bits: u64,
+}
+
+// 单条捕获记录（一次读或写）
+struct CaptureEntry {
+    timestamp_ms: u64,
+    direction: &'static str,
+    data: Vec<u8>,
+}
+
+// 返回给前端的端口统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SerialStats {
+    port_name: String,
+    // open() 调用耗时
+    open_duration_ms: u64,
+    // 打开后到收到第一个字节的时间（反映设备启动时间）
+    first_byte_ms: Option<u64>,
+    uptime_ms: u64,
+    bytes_sent: u64,
+    bytes_received: u64,
+    // 自动重连或写入恢复重新打开句柄的次数
+    reconnects: u32,
+    // 通过控制线软件模拟输出的位数
+    bitbang_bits: u64,
+}
+
+// 打开串口返回的会话信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SerialSession {
+    session_id: String,
+    message: String,
+}
+
+// 生成新的会话 ID
+fn new_session_id() -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let seq = SESSION_COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{:x}-{:x}", timestamp, seq)
+}
+
+// 为串口登记会话，端口已被其他会话占用时拒绝
+fn claim_port(port_name: &str) -> Result<String, String> {
+    let mut sessions = PORT_SESSIONS.lock()
+        .expect("Failed to lock PORT_SESSIONS mutex");
+    if sessions.contains_key(port_name) {
+        return Err(format!("Port {} is already opened by another session", port_name));
+    }
+    let session_id = new_session_id();
+    sessions.insert(port_name.to_string(), session_id.clone());
+    Ok(session_id)
+}
+
+// 释放串口的会话登记；自动关闭计时属于该会话，一并停止
+fn release_port(port_name: &str) {
+    let mut sessions = PORT_SESSIONS.lock()
+        .expect("Failed to lock PORT_SESSIONS mutex");
+    sessions.remove(port_name);
+    drop(sessions);
+    stop_auto_close(port_name);
+}
+
+// 校验会话是否拥有该串口；没有登记会话的端口一律拒绝，不允许绕过归属检查
+fn check_session(port_name: &str, session_id: &str) -> Result<(), String> {
+    let sessions = PORT_SESSIONS.lock()
+        .expect("Failed to lock PORT_SESSIONS mutex");
+    match sessions.get(port_name) {
+        Some(owner) if owner == session_id => Ok(()),
+        Some(_) => Err(format!("Port {} is owned by another session", port_name)),
+        None => Err(format!("Port {} is not open in any session", port_name)),
+    }
+}
+
+// 端口是否已登记会话
+fn is_port_claimed(port_name: &str) -> bool {
+    PORT_SESSIONS.lock()
+        .expect("Failed to lock PORT_SESSIONS mutex")
+        .contains_key(port_name)
 }
 
 // 列出所有可用串口
 #[tauri::command]
-fn list_serial_ports() -> Result<Vec<SerialPortInfo>, String> {
+fn list_serial_ports() -> Result<SerialPortList, String> {
     let mut port_list: Vec<SerialPortInfo> = vec![];
+    let mut warning = None;
+    let mut hardware_supported = true;
     
     // 添加真实串口
     match serialport::available_ports() {
         Ok(ports) => {
-            println!("[DEBUG] 检测到 {} 个物理串口", ports.len());
+            log_message(LogLevel::Debug, format!("检测到 {} 个物理串口", ports.len()));
             for p in &ports {
-                println!("[DEBUG] 串口: {} - {:?}", p.port_name, p.port_type);
+                log_message(LogLevel::Debug, format!("串口: {} - {:?}", p.port_name, p.port_type));
             }
             
             port_list.extend(
@@ -63,46 +629,224 @@ fn list_serial_ports() -> Result<Vec<SerialPortInfo>, String> {
                     SerialPortInfo {
                         port_name: p.port_name.clone(),
                         port_type,
+                        state: "free".to_string(),
                     }
                 })
             );
         }
         Err(e) => {
-            println!("[WARNING] 无法枚举物理串口: {}", e);
-            // 在 Windows 上可能是权限问题或没有驱动
+            log_message(LogLevel::Warning, format!("无法枚举物理串口: {}", e));
+            // 在 Windows 上可能是权限问题或没有驱动，Linux 上常见于 udev 权限
+            warning = Some(format!("Failed to enumerate serial ports: {}", e));
+            hardware_supported = !is_unsupported_platform_error(&e);
         }
     }
     
-    // 添加虚拟串口
-    port_list.push(SerialPortInfo {
-        port_name: "VIRTUAL-COM1".to_string(),
-        port_type: "Virtual Port (Echo)".to_string(),
-    });
-    port_list.push(SerialPortInfo {
-        port_name: "VIRTUAL-COM2".to_string(),
-        port_type: "Virtual Port (Reply)".to_string(),
-    });
-    port_list.push(SerialPortInfo {
-        port_name: "VIRTUAL-COM3".to_string(),
-        port_type: "Virtual Port (Random)".to_string(),
-    });
+    // 添加虚拟串口：内置演示端口加上已配置（新建/改名）或已打开的端口；隐藏时只列出已经打开的
+    let show_virtual = VIRTUAL_PORTS_VISIBLE.load(Ordering::Relaxed);
+    let virtual_ports: Vec<(String, String)> = {
+        let buffers = VIRTUAL_BUFFERS.lock()
+            .expect("Failed to lock VIRTUAL_BUFFERS mutex");
+        let configs = VIRTUAL_CONFIGS.lock()
+            .expect("Failed to lock VIRTUAL_CONFIGS mutex");
+        let mut names: Vec<String> = BUILTIN_VIRTUAL_PORTS.iter().map(|name| name.to_string())
+            .chain(configs.keys().cloned())
+            .chain(buffers.keys().cloned())
+            .filter(|name| show_virtual || buffers.contains_key(name))
+            .collect();
+        names.sort();
+        names.dedup();
+        names.into_iter()
+            .map(|name| {
+                let mode = configs.get(&name)
+                    .and_then(|config| config.mode.clone())
+                    .unwrap_or_else(|| default_virtual_mode(&name).to_string());
+                (name, virtual_port_label(&mode))
+            })
+            .collect()
+    };
+    for (name, port_type) in virtual_ports {
+        port_list.push(SerialPortInfo {
+            port_name: name,
+            port_type,
+            state: "free".to_string(),
+        });
+    }
+
+    // 标注本应用已打开的端口，避免界面状态与后端状态不一致
+    {
+        let ports = SERIAL_PORTS.lock()
+            .expect("Failed to lock SERIAL_PORTS mutex");
+        let buffers = VIRTUAL_BUFFERS.lock()
+            .expect("Failed to lock VIRTUAL_BUFFERS mutex");
+        for info in port_list.iter_mut() {
+            if ports.contains_key(&info.port_name) || buffers.contains_key(&info.port_name) {
+                info.state = "open_here".to_string();
+            }
+        }
+    }
     
-    Ok(port_list)
+    Ok(SerialPortList {
+        ports: port_list,
+        warning,
+        hardware_supported,
+    })
 }
 
-// 打开串口
+// 设置是否列出未打开的虚拟串口（内置演示端口和已配置的端口），隐藏后仍可按名称打开
+#[tauri::command]
+fn set_virtual_ports_visible(visible: bool) -> String {
+    VIRTUAL_PORTS_VISIBLE.store(visible, Ordering::Relaxed);
+    if visible {
+        "Virtual ports are now listed".to_string()
+    } else {
+        "Virtual ports are hidden unless open".to_string()
+    }
+}
+
+// 列出本应用尚未打开的端口（真实串口和虚拟串口）
 #[tauri::command]
-fn open_serial_port(config: SerialConfig) -> Result<String, String> {
+fn list_available_ports() -> Result<SerialPortList, String> {
+    let mut list = list_serial_ports()?;
+    list.ports.retain(|p| p.state == "free");
+    Ok(list)
+}
+
+// 打开串口
+#[tauri::command(async)]
+fn open_serial_port(config: SerialConfig) -> Result<SerialSession, String> {
+    // 登记会话，防止多个窗口互相抢占同一串口
+    let session_id = claim_port(&config.port_name)?;
+
+    match open_port_handle(&config) {
+        Ok(message) => {
+            log_message(LogLevel::Info, message.clone());
+            PORT_CONFIGS.lock()
+                .expect("Failed to lock PORT_CONFIGS mutex")
+                .insert(config.port_name.clone(), config.clone());
+            Ok(SerialSession { session_id, message })
+        }
+        Err(e) => {
+            log_message(LogLevel::Error, format!("打开串口 {} 失败: {}", config.port_name, e));
+            cleanup_port_state(&config.port_name);
+            Err(e)
+        }
+    }
+}
+
+// 打开串口并立即启动后台监听，两步都成功才返回；监听启动失败时关闭端口回滚
+#[tauri::command(async)]
+fn open_and_listen(
+    window: tauri::Window,
+    config: SerialConfig,
+    poll_interval_ms: Option<u64>,
+) -> Result<SerialSession, String> {
+    let session = open_serial_port(config.clone())?;
+
+    if let Err(e) = start_serial_listener(
+        window,
+        config.port_name.clone(),
+        session.session_id.clone(),
+        poll_interval_ms,
+        None,
+    ) {
+        close_port(&config.port_name);
+        return Err(format!("Port opened but listener failed to start: {}", e));
+    }
+
+    Ok(session)
+}
+
+// 按类型打开的端口及其会话
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ResolvedSession {
+    port_name: String,
+    session_id: String,
+    message: String,
+}
+
+// 按类型（usb / bluetooth / pci / unknown）筛选物理串口，按名称排序后打开第 index 个（从 0 开始）
+// config 作为模板，其中的 port_name 会被替换，便于在端口重新编号后仍能自动连接
+#[tauri::command(async)]
+fn open_nth_of_type(
+    port_type: String,
+    index: usize,
+    config: SerialConfig,
+) -> Result<ResolvedSession, String> {
+    let wanted = port_type.to_ascii_lowercase();
+    if !matches!(wanted.as_str(), "usb" | "bluetooth" | "pci" | "unknown") {
+        return Err(format!(
+            "Invalid port type: {} (expected usb, bluetooth, pci or unknown)",
+            port_type
+        ));
+    }
+
+    let mut names: Vec<String> = serialport::available_ports()
+        .map_err(|e| format!("Failed to enumerate serial ports: {}", e))?
+        .into_iter()
+        .filter(|p| match &p.port_type {
+            SerialPortType::UsbPort(_) => wanted == "usb",
+            SerialPortType::BluetoothPort => wanted == "bluetooth",
+            SerialPortType::PciPort => wanted == "pci",
+            SerialPortType::Unknown => wanted == "unknown",
+        })
+        .map(|p| p.port_name)
+        .collect();
+    names.sort();
+    names.dedup();
+
+    let port_name = names.get(index).cloned().ok_or_else(|| {
+        format!(
+            "No {} port at index {} ({} found: {})",
+            wanted,
+            index,
+            names.len(),
+            names.join(", ")
+        )
+    })?;
+
+    let session = open_serial_port(SerialConfig {
+        port_name: port_name.clone(),
+        ..config
+    })?;
+    Ok(ResolvedSession {
+        port_name,
+        session_id: session.session_id,
+        message: session.message,
+    })
+}
+
+// 按配置打开串口句柄（真实或虚拟）
+fn open_port_handle(config: &SerialConfig) -> Result<String, String> {
     // 检查是否是虚拟串口
     if config.port_name.starts_with("VIRTUAL-") {
         // 初始化虚拟串口缓冲区
         let mut buffers = VIRTUAL_BUFFERS.lock()
             .expect("Failed to lock VIRTUAL_BUFFERS mutex");
         buffers.insert(config.port_name.clone(), Vec::new());
+        drop(buffers);
+        record_open(&config.port_name, Duration::ZERO);
+        start_virtual_stream_if_needed(&config.port_name);
         
         return Ok(format!("Virtual port {} opened successfully", config.port_name));
     }
     
+    // 网络串口服务器（tcp://host:port），与真实串口共用同一个管理器
+    if config.port_name.starts_with(TCP_PREFIX) {
+        let started = std::time::Instant::now();
+        let port = TcpSerialPort::connect(
+            &config.port_name,
+            config.baud_rate,
+            Duration::from_millis(OPEN_READ_TIMEOUT_MS),
+        )?;
+        record_open(&config.port_name, started.elapsed());
+        let mut ports = SERIAL_PORTS.lock()
+            .expect("Failed to lock SERIAL_PORTS mutex");
+        ports.insert(config.port_name.clone(), PortHandles::new(&config.port_name, Box::new(port)));
+
+        return Ok(format!("TCP port {} connected successfully", config.port_name));
+    }
+
     // 真实串口逻辑 - Windows 兼容性优化
     // 解析校验位
     let parity = match config.parity.as_str() {
@@ -131,151 +875,5061 @@ fn open_serial_port(config: SerialConfig) -> Result<String, String> {
     // Windows 串口路径格式化（处理 COM10+ 的情况）
     let port_path = normalize_port_path(&config.port_name);
 
-    // 打开串口
-    let port = serialport::new(&port_path, config.baud_rate)
+    let options = config.open_options();
+    let assert_lines = options.assert_lines;
+    let mut builder = serialport::new(&port_path, config.baud_rate)
         .data_bits(data_bits)
         .stop_bits(stop_bits)
         .parity(parity)
-        .timeout(Duration::from_millis(100))
-        .open()
-        .map_err(|e| format!("Failed to open port: {}", e))?;
+        .timeout(Duration::from_millis(OPEN_READ_TIMEOUT_MS));
+    if !assert_lines {
+        // Linux 内核在 open 时仍会短暂拉高 DTR，无法完全避免
+        builder = builder.dtr_on_open(false);
+    }
+
+    // 打开串口，设备刚重新枚举时可能短暂处于忙碌状态，按配置重试
+    let max_attempts = options.retry_count + 1;
+    let mut attempts = 0;
+    let mut port = loop {
+        attempts += 1;
+        let started = std::time::Instant::now();
+        match open_with_builder(&config.port_name, &builder) {
+            Ok(port) => {
+                // 只统计成功的那次 open 调用，打开慢通常意味着驱动问题
+                record_open(&config.port_name, started.elapsed());
+                break port;
+            }
+            Err(e) if attempts < max_attempts && is_port_busy_error(&e) => {
+                log_message(LogLevel::Debug, format!("串口 {} 忙碌，第 {} 次重试: {}", config.port_name, attempts, e));
+                std::thread::sleep(options.retry_delay);
+            }
+            Err(e) if is_unsupported_platform_error(&e) => {
+                return Err(format!(
+                    "Hardware serial ports are not supported on this platform, only virtual ports are available: {}",
+                    e
+                ));
+            }
+            Err(e) => return Err(format!("Failed to open port: {}", e)),
+        }
+    };
+
+    if !assert_lines {
+        let _ = port.write_request_to_send(false);
+        // 清除 HUPCL，关闭时不再拉低 DTR，下次打开就不会产生复位沿
+        #[cfg(unix)]
+        if let Err(e) = port_fd(&config.port_name).and_then(clear_hupcl) {
+            log_message(LogLevel::Warning, format!("无法清除串口 {} 的 HUPCL: {}", config.port_name, e));
+        }
+    }
+
+    // 部分 USB 转串口芯片打开后需要短暂稳定，否则首次读取是乱码
+    if !options.settle.is_zero() {
+        let dtr_low = options.settle_dtr_low;
+        if dtr_low {
+            port.write_data_terminal_ready(false)
+                .map_err(|e| format!("Failed to set DTR: {}", e))?;
+        }
+        std::thread::sleep(options.settle);
+        if dtr_low && assert_lines {
+            port.write_data_terminal_ready(true)
+                .map_err(|e| format!("Failed to set DTR: {}", e))?;
+        }
+        // 丢弃稳定期间收到的杂散数据
+        let _ = port.clear(serialport::ClearBuffer::Input);
+    }
 
     // 保存到全局管理器
     let mut ports = SERIAL_PORTS.lock()
         .expect("Failed to lock SERIAL_PORTS mutex");
-    ports.insert(config.port_name.clone(), port);
+    ports.insert(config.port_name.clone(), PortHandles::new(&config.port_name, port));
+
+    Ok(format!(
+        "Port {} opened successfully after {} attempt(s)",
+        config.port_name, attempts
+    ))
+}
+
+// 打开串口句柄；Unix 下额外记录文件描述符并确保处于 raw 模式
+#[cfg(unix)]
+fn open_with_builder(
+    port_name: &str,
+    builder: &serialport::SerialPortBuilder,
+) -> serialport::Result<Box<dyn SerialPort>> {
+    use std::os::unix::io::AsRawFd;
 
-    Ok(format!("Port {} opened successfully", config.port_name))
+    let port = builder.clone().open_native()?;
+    let fd = port.as_raw_fd();
+    // serialport 打开时已调用 cfmakeraw，这里再次确认，避免驱动默认值干扰二进制数据
+    if let Err(e) = set_termios_raw(fd, true) {
+        log_message(LogLevel::Warning, format!("无法设置串口 {} 为 raw 模式: {}", port_name, e));
+    }
+    let mut fds = PORT_FDS.lock()
+        .expect("Failed to lock PORT_FDS mutex");
+    fds.insert(port_name.to_string(), fd);
+    Ok(Box::new(port))
+}
+
+#[cfg(not(unix))]
+fn open_with_builder(
+    _port_name: &str,
+    builder: &serialport::SerialPortBuilder,
+) -> serialport::Result<Box<dyn SerialPort>> {
+    builder.clone().open()
+}
+
+// 判断打开失败是否为端口忙/拒绝访问（可重试）
+// serialport 在不支持的平台上返回 Unknown + "not implemented"
+fn is_unsupported_platform_error(e: &serialport::Error) -> bool {
+    e.kind() == serialport::ErrorKind::Unknown
+        && e.description.to_lowercase().contains("not implemented")
+}
+
+fn is_port_busy_error(e: &serialport::Error) -> bool {
+    if let serialport::ErrorKind::Io(std::io::ErrorKind::PermissionDenied) = e.kind() {
+        return true;
+    }
+    let desc = e.description.to_lowercase();
+    (desc.contains("access") && desc.contains("denied")) || desc.contains("busy")
 }
 
 // 关闭串口
 #[tauri::command]
-fn close_serial_port(port_name: String) -> Result<String, String> {
+fn close_serial_port(port_name: String, session_id: String) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+
     // 检查是否是虚拟串口
     if port_name.starts_with("VIRTUAL-") {
-        let mut buffers = VIRTUAL_BUFFERS.lock()
-            .expect("Failed to lock VIRTUAL_BUFFERS mutex");
-        buffers.remove(&port_name);
-        
+        close_port(&port_name);
         return Ok(format!("Virtual port {} closed successfully", port_name));
     }
     
     // 真实串口逻辑
-    let mut ports = SERIAL_PORTS.lock()
-        .expect("Failed to lock SERIAL_PORTS mutex");
-    
-    if ports.remove(&port_name).is_some() {
+    if close_port(&port_name) {
         Ok(format!("Port {} closed successfully", port_name))
     } else {
         Err(format!("Port {} not found", port_name))
     }
 }
 
-// 发送数据
+// 关闭串口并清理所有附属状态，返回端口此前是否处于打开状态
+fn close_port(port_name: &str) -> bool {
+    // 先停止监听线程，避免其在端口移除后报错
+    stop_listener(port_name);
+
+    let removed = if port_name.starts_with("VIRTUAL-") {
+        VIRTUAL_BUFFERS.lock()
+            .expect("Failed to lock VIRTUAL_BUFFERS mutex")
+            .remove(port_name)
+            .is_some()
+    } else {
+        SERIAL_PORTS.lock()
+            .expect("Failed to lock SERIAL_PORTS mutex")
+            .remove(port_name)
+            .is_some()
+    };
+    cleanup_port_state(port_name);
+    if removed {
+        log_message(LogLevel::Info, format!("串口 {} 已关闭", port_name));
+    }
+    removed
+}
+
+// 幂等关闭：无论端口是否已打开都返回 Ok，同时清理监听、捕获等后台状态
+// 传入 session_id 时仍校验归属，避免误关其它窗口的端口
+#[tauri::command]
+fn close_serial_port_quiet(port_name: String, session_id: Option<String>) -> Result<String, String> {
+    // 未登记会话的端口视为已关闭，保持幂等
+    if let Some(session_id) = session_id.as_deref().filter(|_| is_port_claimed(&port_name)) {
+        check_session(&port_name, session_id)?;
+    }
+
+    let was_open = close_port(&port_name);
+    CAPTURES.lock()
+        .expect("Failed to lock CAPTURES mutex")
+        .remove(&port_name);
+
+    if was_open {
+        Ok(format!("Port {} closed successfully", port_name))
+    } else {
+        Ok(format!("Port {} was not open", port_name))
+    }
+}
+
+// 批量关闭时单个端口的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ClosedPortReport {
+    port_name: String,
+    // 关闭前刷新输出失败的原因；端口仍会被关闭
+    flush_error: Option<String>,
+}
+
+// 关闭所有已打开的串口（真实、网络、PTY 与虚拟），并停止各自的后台任务
+#[tauri::command(async)]
+fn close_all_ports() -> Vec<ClosedPortReport> {
+    // 先取快照再逐个关闭，避免持锁期间停止监听线程
+    let mut port_names: Vec<String> = SERIAL_PORTS.lock()
+        .expect("Failed to lock SERIAL_PORTS mutex")
+        .keys()
+        .cloned()
+        .collect();
+    port_names.extend(
+        VIRTUAL_BUFFERS.lock()
+            .expect("Failed to lock VIRTUAL_BUFFERS mutex")
+            .keys()
+            .cloned(),
+    );
+    port_names.sort();
+
+    let mut reports = Vec::new();
+    for port_name in port_names {
+        // 停止监听后再刷新，避免与后台读取争用
+        stop_listener(&port_name);
+        let flush_error = if port_name.starts_with("VIRTUAL-") {
+            None
+        } else {
+            port_writer(&port_name)
+                .and_then(|port| {
+                    port.lock()
+                        .expect("Failed to lock port mutex")
+                        .flush()
+                        .map_err(|e| format!("Failed to flush: {}", e))
+                })
+                .err()
+        };
+
+        // 端口可能已被热插拔或其它命令关闭，只报告实际关闭的端口
+        if close_port(&port_name) {
+            reports.push(ClosedPortReport { port_name, flush_error });
+        }
+    }
+
+    log_message(LogLevel::Info, format!("已关闭 {} 个串口", reports.len()));
+    reports
+}
+
+// 创建的 PTY：外部程序连接 path，本应用通过 handle_id 和 session_id 读写
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PtyInfo {
+    path: String,
+    handle_id: String,
+    session_id: String,
+}
+
+// 创建伪终端对（仅 Unix），主设备端以 handle_id 登记到串口管理器并登记会话，
+// 之后可直接用 handle_id 和 session_id 调用读写/termios/关闭命令
+#[tauri::command]
+fn create_pty() -> Result<PtyInfo, String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        let (master, slave) = serialport::TTYPort::pair()
+            .map_err(|e| format!("Failed to create PTY: {}", e))?;
+        let path = slave
+            .name()
+            .ok_or_else(|| "Failed to get PTY slave path".to_string())?;
+        let handle_id = format!("PTY-{}", PTY_COUNTER.fetch_add(1, Ordering::Relaxed));
+        let session_id = claim_port(&handle_id)?;
+
+        // 记录主设备端文件描述符，使 raw 模式、modem 线等 termios 命令对 PTY 同样可用
+        PORT_FDS.lock()
+            .expect("Failed to lock PORT_FDS mutex")
+            .insert(handle_id.clone(), master.as_raw_fd());
+        SERIAL_PORTS.lock()
+            .expect("Failed to lock SERIAL_PORTS mutex")
+            .insert(handle_id.clone(), PortHandles::new(&handle_id, Box::new(master)));
+        PTY_SLAVES.lock()
+            .expect("Failed to lock PTY_SLAVES mutex")
+            .insert(handle_id.clone(), slave);
+        record_open(&handle_id, Duration::ZERO);
+
+        log_message(LogLevel::Info, format!("已创建 PTY {}，从设备路径 {}", handle_id, path));
+        Ok(PtyInfo { path, handle_id, session_id })
+    }
+
+    #[cfg(not(unix))]
+    {
+        Err("PTY is only supported on Unix".to_string())
+    }
+}
+
+// 记录端口打开耗时，并开始统计
+fn record_open(port_name: &str, open_duration: Duration) {
+    let mut stats = PORT_STATS.lock()
+        .expect("Failed to lock PORT_STATS mutex");
+    stats.insert(port_name.to_string(), PortStats {
+        opened_at: std::time::Instant::now(),
+        open_duration_ms: open_duration.as_millis() as u64,
+        first_byte_ms: None,
+        bytes_sent: 0,
+        bytes_received: 0,
+        last_io: std::time::Instant::now(),
+        reconnects: 0,
+        bitbang_bits: 0,
+    });
+    drop(stats);
+
+    // 重新打开时开始新的捕获
+    CAPTURES.lock()
+        .expect("Failed to lock CAPTURES mutex")
+        .insert(port_name.to_string(), std::collections::VecDeque::new());
+}
+
+// 追加一条捕获记录，超出上限时丢弃最旧的记录
+fn record_capture(port_name: &str, direction: &'static str, data: &[u8]) {
+    let timestamp_ms = unix_millis();
+
+    let mut captures = CAPTURES.lock()
+        .expect("Failed to lock CAPTURES mutex");
+    let capture = captures.entry(port_name.to_string()).or_default();
+    if capture.len() >= MAX_CAPTURE_ENTRIES {
+        capture.pop_front();
+    }
+    capture.push_back(CaptureEntry {
+        timestamp_ms,
+        direction,
+        data: data.to_vec(),
+    });
+}
+
+// 记录发送的数据
+fn record_tx(port_name: &str, data: &[u8]) {
+    if data.is_empty() {
+        return;
+    }
+    {
+        let mut stats = PORT_STATS.lock()
+            .expect("Failed to lock PORT_STATS mutex");
+        if let Some(entry) = stats.get_mut(port_name) {
+            entry.bytes_sent += data.len() as u64;
+            entry.last_io = std::time::Instant::now();
+        }
+    }
+    record_capture(port_name, "TX", data);
+}
+
+// 记录控制线上模拟输出的位数：这些位没有经过数据线，不计入 bytes_sent 和捕获
+fn record_bitbang(port_name: &str, bits: usize) {
+    let mut stats = PORT_STATS.lock()
+        .expect("Failed to lock PORT_STATS mutex");
+    if let Some(entry) = stats.get_mut(port_name) {
+        entry.bitbang_bits += bits as u64;
+        entry.last_io = std::time::Instant::now();
+    }
+}
+
+// 记录接收的数据，首次收到数据时记录首字节时间
+fn record_rx(port_name: &str, data: &[u8]) {
+    if data.is_empty() {
+        return;
+    }
+    {
+        let mut stats = PORT_STATS.lock()
+            .expect("Failed to lock PORT_STATS mutex");
+        if let Some(entry) = stats.get_mut(port_name) {
+            if entry.first_byte_ms.is_none() {
+                entry.first_byte_ms = Some(entry.opened_at.elapsed().as_millis() as u64);
+            }
+            entry.bytes_received += data.len() as u64;
+            entry.last_io = std::time::Instant::now();
+        }
+    }
+    if let Some(checksum) = STREAM_CHECKSUMS.lock()
+        .expect("Failed to lock STREAM_CHECKSUMS mutex")
+        .get_mut(port_name)
+    {
+        checksum.update(data);
+    }
+    record_capture(port_name, "RX", data);
+}
+
+// 开始对该端口后续接收的数据计算增量校验（sum8 / xor8 / crc16_modbus），
+// 已在计算的会被重置
+#[tauri::command]
+fn start_stream_checksum(port_name: String, session_id: String, algo: String) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+
+    let checksum = RunningChecksum::new(&algo)?;
+    STREAM_CHECKSUMS.lock()
+        .expect("Failed to lock STREAM_CHECKSUMS mutex")
+        .insert(port_name.clone(), checksum);
+    Ok(format!("Started {} checksum on {}", algo, port_name))
+}
+
+// 结束增量校验并返回校验字节（crc16 低字节在前）
+#[tauri::command]
+fn finalize_stream_checksum(port_name: String, session_id: String) -> Result<Vec<u8>, String> {
+    check_session(&port_name, &session_id)?;
+
+    STREAM_CHECKSUMS.lock()
+        .expect("Failed to lock STREAM_CHECKSUMS mutex")
+        .remove(&port_name)
+        .map(|checksum| checksum.finalize())
+        .ok_or_else(|| format!("No stream checksum running on {}", port_name))
+}
+
+// CSV 字段转义：包含逗号、引号或换行时加引号
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// 导出捕获数据为 CSV：timestamp_ms, direction, hex, ascii
+#[tauri::command]
+fn export_capture_csv(port_name: String, session_id: String, file_path: String) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+
+    let mut csv = String::from("timestamp_ms,direction,hex,ascii\n");
+    let count = {
+        let captures = CAPTURES.lock()
+            .expect("Failed to lock CAPTURES mutex");
+        let capture = captures
+            .get(&port_name)
+            .ok_or_else(|| format!("No capture for port {}", port_name))?;
+
+        for entry in capture {
+            // 不可打印字符显示为 '.'
+            let ascii: String = entry.data
+                .iter()
+                .map(|&b| if (0x20..0x7F).contains(&b) { b as char } else { '.' })
+                .collect();
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                entry.timestamp_ms,
+                entry.direction,
+                bytes_to_hex_string(&entry.data),
+                csv_field(&ascii)
+            ));
+        }
+        capture.len()
+    };
+
+    std::fs::write(&file_path, csv)
+        .map_err(|e| format!("Failed to write {}: {}", file_path, e))?;
+
+    log_message(LogLevel::Debug, format!("Exported {} capture entries of {} to {}", count, port_name, file_path));
+    Ok(format!("Exported {} entries to {}", count, file_path))
+}
+
+// 获取端口统计信息
+#[tauri::command]
+fn get_serial_stats(port_name: String, session_id: String) -> Result<SerialStats, String> {
+    check_session(&port_name, &session_id)?;
+
+    let stats = PORT_STATS.lock()
+        .expect("Failed to lock PORT_STATS mutex");
+    let entry = stats
+        .get(&port_name)
+        .ok_or_else(|| format!("Port {} not found", port_name))?;
+
+    Ok(SerialStats {
+        port_name: port_name.clone(),
+        open_duration_ms: entry.open_duration_ms,
+        first_byte_ms: entry.first_byte_ms,
+        uptime_ms: entry.opened_at.elapsed().as_millis() as u64,
+        bytes_sent: entry.bytes_sent,
+        bytes_received: entry.bytes_received,
+        reconnects: entry.reconnects,
+        bitbang_bits: entry.bitbang_bits,
+    })
+}
+
+// 取出 HashMap 的键并排序，便于快照对比
+fn sorted_keys<V>(map: &HashMap<String, V>) -> Vec<String> {
+    let mut keys: Vec<String> = map.keys().cloned().collect();
+    keys.sort();
+    keys
+}
+
+// 导出后端管理状态的快照，供排查问题使用
+// 不包含任何收发数据（捕获、缓冲、自动应答内容），只给出数量
+#[tauri::command]
+fn debug_snapshot() -> serde_json::Value {
+    use serde_json::json;
+
+    // 逐个加锁取数据，不嵌套持锁
+    let open_ports = sorted_keys(&SERIAL_PORTS.lock()
+        .expect("Failed to lock SERIAL_PORTS mutex"));
+    let virtual_ports: Vec<(String, usize)> = {
+        let buffers = VIRTUAL_BUFFERS.lock()
+            .expect("Failed to lock VIRTUAL_BUFFERS mutex");
+        sorted_keys(&buffers)
+            .into_iter()
+            .map(|name| {
+                let pending = buffers[&name].len();
+                (name, pending)
+            })
+            .collect()
+    };
+    let configs = PORT_CONFIGS.lock()
+        .expect("Failed to lock PORT_CONFIGS mutex")
+        .clone();
+    let stats: HashMap<String, serde_json::Value> = PORT_STATS.lock()
+        .expect("Failed to lock PORT_STATS mutex")
+        .iter()
+        .map(|(name, entry)| (name.clone(), json!({
+            "open_duration_ms": entry.open_duration_ms,
+            "first_byte_ms": entry.first_byte_ms,
+            "uptime_ms": entry.opened_at.elapsed().as_millis() as u64,
+            "bytes_sent": entry.bytes_sent,
+            "bytes_received": entry.bytes_received,
+            "reconnects": entry.reconnects,
+            "bitbang_bits": entry.bitbang_bits,
+        })))
+        .collect();
+    let read_timeouts = READ_TIMEOUTS.lock()
+        .expect("Failed to lock READ_TIMEOUTS mutex")
+        .clone();
+    let virtual_configs: HashMap<String, serde_json::Value> = VIRTUAL_CONFIGS.lock()
+        .expect("Failed to lock VIRTUAL_CONFIGS mutex")
+        .iter()
+        .map(|(name, config)| (name.clone(), json!({
+            "mode": config.mode.as_deref().unwrap_or(default_virtual_mode(name)),
+            "fault": config.fault,
+            "responses": config.responses.len(),
+            "stream_interval_ms": config.stream_interval_ms,
+        })))
+        .collect();
+    let sessions = sorted_keys(&PORT_SESSIONS.lock()
+        .expect("Failed to lock PORT_SESSIONS mutex"));
+    let listeners = sorted_keys(&LISTENERS.lock()
+        .expect("Failed to lock LISTENERS mutex"));
+    let virtual_streams = sorted_keys(&VIRTUAL_STREAMS.lock()
+        .expect("Failed to lock VIRTUAL_STREAMS mutex"));
+    let frame_parsers: HashMap<String, FrameSpec> = FRAME_PARSERS.lock()
+        .expect("Failed to lock FRAME_PARSERS mutex")
+        .iter()
+        .map(|(name, parser)| (name.clone(), parser.spec.clone()))
+        .collect();
+    let auto_reconnect: HashMap<String, serde_json::Value> = AUTO_RECONNECT.lock()
+        .expect("Failed to lock AUTO_RECONNECT mutex")
+        .iter()
+        .map(|(name, policy)| (name.clone(), json!({
+            "max_attempts": policy.max_attempts,
+            "delay_ms": policy.delay_ms,
+        })))
+        .collect();
+    let captures: HashMap<String, usize> = CAPTURES.lock()
+        .expect("Failed to lock CAPTURES mutex")
+        .iter()
+        .map(|(name, capture)| (name.clone(), capture.len()))
+        .collect();
+
+    let real_ports: Vec<serde_json::Value> = open_ports
+        .iter()
+        .map(|name| json!({
+            "port_name": name,
+            "config": configs.get(name),
+            "stats": stats.get(name),
+            "read_timeout_ms": read_timeouts.get(name),
+        }))
+        .collect();
+    let virtual_ports: Vec<serde_json::Value> = virtual_ports
+        .iter()
+        .map(|(name, pending)| json!({
+            "port_name": name,
+            "pending_bytes": pending,
+            "config": virtual_configs.get(name),
+            "stats": stats.get(name),
+        }))
+        .collect();
+
+    json!({
+        "real_ports": real_ports,
+        "virtual_ports": virtual_ports,
+        "virtual_configs": virtual_configs,
+        "sessions": sessions,
+        "tasks": {
+            "listeners": listeners,
+            "virtual_streams": virtual_streams,
+            "frame_parsers": frame_parsers,
+            "auto_reconnect": auto_reconnect,
+        },
+        "capture_entries": captures,
+        "log_level": LogLevel::from_u8(LOG_LEVEL.load(Ordering::Relaxed)).label(),
+    })
+}
+
+// 清理端口关闭后残留的附属状态（会话、文件描述符、终端行缓冲等）
+fn cleanup_port_state(port_name: &str) {
+    stop_listener(port_name);
+    stop_virtual_stream(port_name);
+    release_port(port_name);
+    forget_port_fd(port_name);
+    TERMINAL_LINES.lock()
+        .expect("Failed to lock TERMINAL_LINES mutex")
+        .remove(port_name);
+    FRAME_PARSERS.lock()
+        .expect("Failed to lock FRAME_PARSERS mutex")
+        .remove(port_name);
+    READ_SCRATCH.lock()
+        .expect("Failed to lock READ_SCRATCH mutex")
+        .remove(port_name);
+    PORT_STATS.lock()
+        .expect("Failed to lock PORT_STATS mutex")
+        .remove(port_name);
+    READ_TIMEOUTS.lock()
+        .expect("Failed to lock READ_TIMEOUTS mutex")
+        .remove(port_name);
+    SAMPLE_REMAINDERS.lock()
+        .expect("Failed to lock SAMPLE_REMAINDERS mutex")
+        .remove(port_name);
+    AUTO_RECONNECT.lock()
+        .expect("Failed to lock AUTO_RECONNECT mutex")
+        .remove(port_name);
+    STREAM_CHECKSUMS.lock()
+        .expect("Failed to lock STREAM_CHECKSUMS mutex")
+        .remove(port_name);
+    DETECTED_ENCODINGS.lock()
+        .expect("Failed to lock DETECTED_ENCODINGS mutex")
+        .remove(port_name);
+    PAUSED_PORTS.lock()
+        .expect("Failed to lock PAUSED_PORTS mutex")
+        .remove(port_name);
+    cancel_signal_log(port_name);
+    #[cfg(feature = "test-inject")]
+    INJECTED_READS.lock()
+        .expect("Failed to lock INJECTED_READS mutex")
+        .remove(port_name);
+    #[cfg(unix)]
+    PTY_SLAVES.lock()
+        .expect("Failed to lock PTY_SLAVES mutex")
+        .remove(port_name);
+}
+
+// 发送数据
+#[tauri::command]
+fn write_serial_data(
+    port_name: String,
+    session_id: String,
+    data: String,
+    is_hex: bool,
+    options: Option<WriteOptions>,
+) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+    let options = options.unwrap_or_default();
+
+    // 可选：按分隔符拆分为多条，空段跳过
+    let segments: Vec<&str> = match options.split_on.as_deref() {
+        Some(delimiter) if !delimiter.is_empty() => data
+            .split(delimiter)
+            .filter(|segment| !segment.trim().is_empty())
+            .collect(),
+        _ => vec![data.as_str()],
+    };
+
+    // 先全部编码，任一段有误时不发送任何数据
+    let mut frames = Vec::with_capacity(segments.len());
+    for segment in &segments {
+        let bytes_to_send = encode_payload(segment, is_hex, &options)?;
+        // 空数据（包括只含空白的 HEX 字符串）视为错误，避免掩盖界面输入问题
+        if bytes_to_send.is_empty() {
+            return Err("nothing to send".to_string());
+        }
+        frames.push(bytes_to_send);
+    }
+    if frames.is_empty() {
+        return Err("nothing to send".to_string());
+    }
+    let policy = options.on_write_error.as_deref().unwrap_or("abort");
+    if !matches!(policy, "abort" | "flush_and_retry" | "reset_port") {
+        return Err(format!(
+            "Invalid on_write_error policy: {} (expected abort, flush_and_retry or reset_port)",
+            policy
+        ));
+    }
+
+    let write_timeout = options.write_timeout_ms.map(Duration::from_millis);
+    let inter_frame_delay = Duration::from_millis(options.inter_frame_delay_ms.unwrap_or(0));
+    let mut sent = 0;
+    let mut recoveries = 0;
+    for (index, (segment, bytes_to_send)) in segments.iter().zip(&frames).enumerate() {
+        if index > 0 && !inter_frame_delay.is_zero() {
+            std::thread::sleep(inter_frame_delay);
+        }
+        let error = match write_port_bytes_timeout(&port_name, bytes_to_send, segment, write_timeout) {
+            Ok(n) => {
+                sent += n;
+                continue;
+            }
+            Err(e) if policy == "abort" => return Err(e),
+            Err(e) => e,
+        };
+
+        log_message(LogLevel::Warning, format!("串口 {} 写入失败，尝试 {} 恢复: {}", port_name, policy, error));
+        recover_write(&port_name, policy)
+            .map_err(|e| format!("{} (recovery {} failed: {})", error, policy, e))?;
+        sent += write_port_bytes_timeout(&port_name, bytes_to_send, segment, write_timeout)
+            .map_err(|e| format!("{} (retry after {} failed: {})", error, policy, e))?;
+        recoveries += 1;
+    }
+    let mut message = finish_write(&port_name, &options, sent)?;
+    if frames.len() > 1 {
+        message = format!("{} in {} segments", message, frames.len());
+    }
+    if recoveries > 0 {
+        message = format!("{}, recovered via {} {} time(s)", message, policy, recoveries);
+    }
+    Ok(message)
+}
+
+// 执行写入失败后的恢复动作，虚拟串口无需恢复直接重试
+fn recover_write(port_name: &str, policy: &str) -> Result<(), String> {
+    if port_name.starts_with("VIRTUAL-") {
+        return Ok(());
+    }
+    match policy {
+        "flush_and_retry" => {
+            let port = port_writer(port_name)?;
+            let port = port.lock()
+                .expect("Failed to lock port mutex");
+            port.clear(serialport::ClearBuffer::All)
+                .map_err(|e| format!("Failed to clear buffers: {}", e))
+        }
+        "reset_port" => reopen_port(port_name),
+        _ => Ok(()),
+    }
+}
+
+// 按最近一次的打开配置重新打开端口，会话、统计和捕获记录保持不变，成功时重连计数加一
+fn reopen_port(port_name: &str) -> Result<(), String> {
+    let config = PORT_CONFIGS.lock()
+        .expect("Failed to lock PORT_CONFIGS mutex")
+        .get(port_name)
+        .cloned()
+        .ok_or_else(|| format!("No saved configuration for {}", port_name))?;
+
+    // 先释放旧句柄，否则部分平台无法重新打开同一设备
+    SERIAL_PORTS.lock()
+        .expect("Failed to lock SERIAL_PORTS mutex")
+        .remove(port_name);
+    forget_port_fd(port_name);
+
+    // 重新打开会重置统计和捕获，这里保留之前的记录
+    let stats = PORT_STATS.lock()
+        .expect("Failed to lock PORT_STATS mutex")
+        .remove(port_name);
+    let capture = CAPTURES.lock()
+        .expect("Failed to lock CAPTURES mutex")
+        .remove(port_name);
+    let result = open_port_handle(&config);
+    if let Some(mut stats) = stats {
+        if result.is_ok() {
+            stats.reconnects += 1;
+            // 新句柄从现在开始计时（自动关闭的空闲/最长时长以此为准）
+            stats.opened_at = std::time::Instant::now();
+            stats.last_io = stats.opened_at;
+        }
+        PORT_STATS.lock()
+            .expect("Failed to lock PORT_STATS mutex")
+            .insert(port_name.to_string(), stats);
+    }
+    if let Some(capture) = capture {
+        CAPTURES.lock()
+            .expect("Failed to lock CAPTURES mutex")
+            .insert(port_name.to_string(), capture);
+    }
+    result.map(|_| ())
+}
+
+// 写入完成后的收尾：可选等待输出缓冲清空，生成结果信息
+fn finish_write(port_name: &str, options: &WriteOptions, sent: usize) -> Result<String, String> {
+    // 可选：等待输出缓冲清空，确认数据已真正发出
+    if options.confirm_drain {
+        let timeout = Duration::from_millis(options.drain_timeout_ms.unwrap_or(1000));
+        let pending = wait_for_drain(port_name, timeout)?;
+        if pending > 0 {
+            return Ok(format!(
+                "Sent {} bytes (not drained: {} bytes still pending)",
+                sent, pending
+            ));
+        }
+        return Ok(format!("Sent {} bytes (drained)", sent));
+    }
+
+    Ok(format!("Sent {} bytes", sent))
+}
+
+// 发送数据编码流程：HEX 解析/字节序，或转义解析 + 文本编码，最后追加行尾
+fn encode_payload(data: &str, is_hex: bool, options: &WriteOptions) -> Result<Vec<u8>, String> {
+    let mut bytes = if is_hex {
+        let bytes = hex_string_to_bytes(data)
+            .map_err(|e| format!("Invalid hex string: {}", e))?;
+        apply_byte_order(bytes, options.byte_order.as_deref().unwrap_or("none"))?
+    } else {
+        let encoding = options.encoding.as_deref().unwrap_or("utf-8");
+        if options.parse_escapes {
+            let mut bytes = Vec::new();
+            for segment in parse_escape_segments(data)? {
+                match segment {
+                    EscapeSegment::Text(text) => bytes.extend(encode_text(&text, encoding)?),
+                    EscapeSegment::Byte(byte) => bytes.push(byte),
+                }
+            }
+            bytes
+        } else {
+            encode_text(data, encoding)?
+        }
+    };
+
+    if let Some(length_field) = &options.length_field {
+        insert_length_field(&mut bytes, length_field)?;
+    }
+
+    bytes.extend_from_slice(line_ending_bytes(options.line_ending.as_deref().unwrap_or("none"))?);
+    Ok(bytes)
+}
+
+// 计算长度并插入到负载的 offset 位置（行尾不计入长度）
+fn insert_length_field(bytes: &mut Vec<u8>, field: &LengthField) -> Result<(), String> {
+    if !matches!(field.size, 1 | 2 | 4) {
+        return Err(format!("Length field size must be 1, 2 or 4, got {}", field.size));
+    }
+    if field.offset > bytes.len() {
+        return Err(format!(
+            "Length field offset {} is beyond the payload length {}",
+            field.offset,
+            bytes.len()
+        ));
+    }
+
+    let mut length = (bytes.len() - field.offset) as u64;
+    if field.includes_self {
+        length += field.size as u64;
+    }
+    let max = (1u64 << (field.size * 8)) - 1;
+    if length > max {
+        return Err(format!(
+            "Length {} does not fit in a {}-byte length field",
+            length, field.size
+        ));
+    }
+
+    let be_bytes = length.to_be_bytes();
+    let mut encoded = be_bytes[8 - field.size..].to_vec();
+    match field.endian.as_deref().unwrap_or("be") {
+        "be" => {}
+        "le" => encoded.reverse(),
+        other => return Err(format!("Unsupported endian: {}", other)),
+    }
+
+    bytes.splice(field.offset..field.offset, encoded);
+    Ok(())
+}
+
+// 转义解析结果：普通文本或 \xHH 指定的原始字节
+enum EscapeSegment {
+    Text(String),
+    Byte(u8),
+}
+
+// 解析文本中的转义序列
+fn parse_escape_segments(data: &str) -> Result<Vec<EscapeSegment>, String> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut chars = data.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('r') => text.push('\r'),
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some('0') => text.push('\0'),
+            Some('\\') => text.push('\\'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == 2)
+                    .ok_or_else(|| format!("Invalid escape sequence: \\x{}", hex))?;
+                if !text.is_empty() {
+                    segments.push(EscapeSegment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(EscapeSegment::Byte(byte));
+            }
+            Some(other) => return Err(format!("Invalid escape sequence: \\{}", other)),
+            None => return Err("Trailing backslash in input".to_string()),
+        }
+    }
+    if !text.is_empty() {
+        segments.push(EscapeSegment::Text(text));
+    }
+    Ok(segments)
+}
+
+// 按编码名称将文本编码为字节
+fn encode_text(text: &str, encoding: &str) -> Result<Vec<u8>, String> {
+    let encoding = encoding_rs::Encoding::for_label(encoding.trim().as_bytes())
+        .ok_or_else(|| format!("Unsupported encoding: {}", encoding))?;
+
+    // encoding_rs 按 Encoding Standard 不支持编码为 UTF-16，需单独处理
+    if encoding == encoding_rs::UTF_16LE {
+        return Ok(text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect());
+    }
+    if encoding == encoding_rs::UTF_16BE {
+        return Ok(text.encode_utf16().flat_map(|u| u.to_be_bytes()).collect());
+    }
+
+    // 不使用替换字符：无法表示的字符直接报错，避免向设备发送错误字节
+    let mut encoder = encoding.new_encoder();
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut remaining = text;
+    loop {
+        let (result, read) =
+            encoder.encode_from_utf8_to_vec_without_replacement(remaining, &mut bytes, true);
+        remaining = &remaining[read..];
+        match result {
+            encoding_rs::EncoderResult::InputEmpty => return Ok(bytes),
+            encoding_rs::EncoderResult::OutputFull => bytes.reserve(remaining.len() * 4 + 16),
+            encoding_rs::EncoderResult::Unmappable(c) => {
+                return Err(format!(
+                    "Character '{}' (U+{:04X}) cannot be encoded in {}",
+                    c,
+                    c as u32,
+                    encoding.name()
+                ));
+            }
+        }
+    }
+}
+
+// 预览发送内容：执行完整编码流程但不访问任何端口
+#[tauri::command]
+fn preview_write(
+    data: String,
+    is_hex: bool,
+    line_ending: Option<String>,
+    parse_escapes: Option<bool>,
+    encoding: Option<String>,
+) -> Result<Vec<u8>, String> {
+    let options = WriteOptions {
+        line_ending,
+        parse_escapes: parse_escapes.unwrap_or(false),
+        encoding,
+        ..Default::default()
+    };
+    encode_payload(&data, is_hex, &options)
+}
+
+// 轮询输出缓冲直到清空或超时，返回剩余未发送的字节数
+fn wait_for_drain(port_name: &str, timeout: Duration) -> Result<u32, String> {
+    use std::time::Instant;
+
+    // 虚拟串口没有输出缓冲
+    if port_name.starts_with("VIRTUAL-") {
+        return Ok(0);
+    }
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        let pending = {
+            let port = port_writer(port_name)?;
+            let port = port.lock()
+                .expect("Failed to lock port mutex");
+            port.bytes_to_write()
+                .map_err(|e| format!("Failed to query output buffer: {}", e))?
+        };
+        if pending == 0 || Instant::now() >= deadline {
+            return Ok(pending);
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+// 解析读取超时：未指定或为 0 时使用全局默认值
+fn resolve_read_timeout(timeout_ms: Option<u64>) -> u64 {
+    match timeout_ms {
+        Some(ms) if ms > 0 => ms,
+        _ => DEFAULT_READ_TIMEOUT_MS.load(Ordering::Relaxed),
+    }
+}
+
+// 设置读取命令的默认超时（毫秒），timeout_ms 省略或为 0 的读取使用该值
+#[tauri::command]
+fn set_default_timeout(ms: u64) -> Result<String, String> {
+    if ms == 0 {
+        return Err("Default timeout must be greater than 0".to_string());
+    }
+    DEFAULT_READ_TIMEOUT_MS.store(ms, Ordering::Relaxed);
+    Ok(format!("Default read timeout set to {} ms", ms))
+}
+
+// 读取数据；指定 min_bytes 时持续累积直到收到至少 min_bytes 字节或超时，超时返回已收到的部分
+// 指定 coalesce_gap_us 时，收到数据后继续读取，间隔小于该值的后续数据合并为一块返回，
+// 出现更长的静默或到达超时才返回；真实串口的读超时以毫秒为单位，间隔精度约 1ms
+#[tauri::command]
+fn read_serial_data(
+    port_name: String,
+    session_id: String,
+    timeout_ms: Option<u64>,
+    min_bytes: Option<usize>,
+    coalesce_gap_us: Option<u64>,
+) -> Result<Vec<u8>, String> {
+    check_session(&port_name, &session_id)?;
+    let timeout_ms = resolve_read_timeout(timeout_ms);
+
+    let min_bytes = min_bytes.unwrap_or(0);
+    let coalesce_gap = coalesce_gap_us.filter(|&us| us > 0).map(Duration::from_micros);
+    if min_bytes <= 1 && coalesce_gap.is_none() {
+        return read_port_bytes(&port_name, timeout_ms);
+    }
+
+    let deadline = std::time::Instant::now() + Duration::from_millis(timeout_ms);
+    let mut data = read_port_chunk(&port_name, deadline)?;
+    while data.len() < min_bytes.max(1) && std::time::Instant::now() < deadline {
+        data.extend(read_port_chunk(&port_name, deadline)?);
+    }
+
+    if let Some(gap) = coalesce_gap.filter(|_| !data.is_empty()) {
+        let mut last_arrival = std::time::Instant::now();
+        loop {
+            let gap_deadline = (last_arrival + gap).min(deadline);
+            if std::time::Instant::now() >= gap_deadline {
+                break;
+            }
+            let more = read_port_chunk(&port_name, gap_deadline)?;
+            if !more.is_empty() {
+                data.extend(more);
+                last_arrival = std::time::Instant::now();
+            }
+        }
+    }
+    Ok(data)
+}
+
+// 立即返回系统缓冲中已有的全部数据，不等待（没有数据时返回空数组）
+#[tauri::command]
+fn read_available(port_name: String, session_id: String) -> Result<Vec<u8>, String> {
+    check_session(&port_name, &session_id)?;
+    // 虚拟串口读取本身就不等待
+    if port_name.starts_with("VIRTUAL-") {
+        return read_port_bytes(&port_name, 0);
+    }
+
+    let port = port_reader(&port_name)?;
+    let mut port = port.lock()
+        .expect("Failed to lock port mutex");
+
+    let available = port.bytes_to_read()
+        .map_err(|e| format!("Failed to query pending bytes: {}", e))? as usize;
+    if available == 0 {
+        return Ok(vec![]);
+    }
+
+    let mut data = vec![0u8; available];
+    let mut filled = 0;
+    while filled < available {
+        match port.read(&mut data[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            // 数据已在缓冲中，超时说明计数已被其它读取消耗，返回已读部分
+            Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => break,
+            Err(e) => return Err(format!("Failed to read data: {}", e)),
+        }
+    }
+    drop(port);
+
+    data.truncate(filled);
+    record_rx(&port_name, &data);
+    Ok(data)
+}
+
+// 批量读取中的单个请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MultiReadRequest {
+    port_name: String,
+    session_id: String,
+    // 未指定或为 0 时使用默认超时
+    timeout_ms: Option<u64>,
+}
+
+// 批量读取中的单个结果，读取失败时 bytes 为空并给出 error
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MultiReadResult {
+    port_name: String,
+    bytes: Vec<u8>,
+    error: Option<String>,
+}
+
+// 一次调用读取多个端口；各端口并行读取，总耗时约为最长的那个超时
+#[tauri::command(async)]
+fn read_serial_multi(requests: Vec<MultiReadRequest>) -> Vec<MultiReadResult> {
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = requests
+            .iter()
+            .map(|request| {
+                scope.spawn(move || {
+                    check_session(&request.port_name, &request.session_id)?;
+                    read_port_bytes(&request.port_name, resolve_read_timeout(request.timeout_ms))
+                })
+            })
+            .collect();
+
+        requests
+            .iter()
+            .zip(handles)
+            .map(|(request, handle)| {
+                let result = handle
+                    .join()
+                    .unwrap_or_else(|_| Err("Read thread panicked".to_string()));
+                match result {
+                    Ok(bytes) => MultiReadResult {
+                        port_name: request.port_name.clone(),
+                        bytes,
+                        error: None,
+                    },
+                    Err(error) => MultiReadResult {
+                        port_name: request.port_name.clone(),
+                        bytes: Vec::new(),
+                        error: Some(error),
+                    },
+                }
+            })
+            .collect()
+    })
+}
+
+// 同一次读取的原始字节、HEX 和解码文本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DualReadResult {
+    bytes: Vec<u8>,
+    hex: String,
+    text: String,
+    // 实际用于解码的编码
+    encoding: String,
+}
+
+// 读取一次数据并同时返回 HEX 与文本，保证两种视图对应同一批字节
+// 多字节字符被拆在两次读取之间时，文本中会出现替换字符
+// auto_detect_encoding 为 true 时按开头的 UTF-8/UTF-16 BOM 选择编码并去掉 BOM，
+// 识别结果在端口关闭前保持有效；没有 BOM 时使用 encoding
+#[tauri::command]
+fn read_serial_dual(
+    port_name: String,
+    session_id: String,
+    timeout_ms: Option<u64>,
+    encoding: Option<String>,
+    newline_translate: Option<String>,
+    auto_detect_encoding: Option<bool>,
+) -> Result<DualReadResult, String> {
+    check_session(&port_name, &session_id)?;
+    let timeout_ms = resolve_read_timeout(timeout_ms);
+    let encoding = encoding.unwrap_or_else(|| "utf-8".to_string());
+    let newline_translate = newline_translate.unwrap_or_else(|| "none".to_string());
+    // 读取前先校验参数，避免读走的数据因参数错误而丢失
+    translate_newlines("", &newline_translate)?;
+    let configured = encoding_rs::Encoding::for_label(encoding.trim().as_bytes())
+        .ok_or_else(|| format!("Unsupported encoding: {}", encoding))?;
+
+    let bytes = read_port_bytes(&port_name, timeout_ms)?;
+    let (text, used) = if auto_detect_encoding.unwrap_or(false) {
+        decode_with_bom_detection(&port_name, &bytes, configured)
+    } else {
+        let (text, used, _) = configured.decode(&bytes);
+        (text.into_owned(), used)
+    };
+    let text = escape_control_chars(&translate_newlines(&text, &newline_translate)?);
+
+    Ok(DualReadResult {
+        hex: bytes_to_hex_string(&bytes),
+        text,
+        bytes,
+        encoding: used.name().to_string(),
+    })
+}
+
+// 按 BOM 识别编码并解码（去掉 BOM）；之前已识别过的端口沿用识别结果，否则使用 fallback
+fn decode_with_bom_detection(
+    port_name: &str,
+    bytes: &[u8],
+    fallback: &'static encoding_rs::Encoding,
+) -> (String, &'static encoding_rs::Encoding) {
+    let mut detected = DETECTED_ENCODINGS.lock()
+        .expect("Failed to lock DETECTED_ENCODINGS mutex");
+    let (encoding, body) = match encoding_rs::Encoding::for_bom(bytes) {
+        Some((encoding, bom_len)) => {
+            detected.insert(port_name.to_string(), encoding);
+            (encoding, &bytes[bom_len..])
+        }
+        None => (detected.get(port_name).copied().unwrap_or(fallback), bytes),
+    };
+    drop(detected);
+    (encoding.decode_without_bom_handling(body).0.into_owned(), encoding)
+}
+
+// 解码后的换行转换：none / cr_to_lf（每个 CR 变为 LF）/ crlf_to_lf（CRLF 合并为 LF）
+fn translate_newlines(text: &str, mode: &str) -> Result<String, String> {
+    match mode {
+        "none" => Ok(text.to_string()),
+        "cr_to_lf" => Ok(text.replace('\r', "\n")),
+        "crlf_to_lf" => Ok(text.replace("\r\n", "\n")),
+        other => Err(format!("Unsupported newline translation: {}", other)),
+    }
+}
+
+// 将不可见控制字符转义为 \xHH（保留换行、回车和制表符）
+fn escape_control_chars(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\n' | '\r' | '\t' => escaped.push(c),
+            c if (c as u32) < 0x20 || c == '\u{7F}' => {
+                escaped.push_str(&format!("\\x{:02X}", c as u32));
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// 向串口写入字节（虚拟或真实），text 为原始输入文本，供虚拟 Reply 模式使用
+fn write_port_bytes(port_name: &str, bytes_to_send: &[u8], text: &str) -> Result<usize, String> {
+    write_port_bytes_timeout(port_name, bytes_to_send, text, None)
+}
+
+// 向串口写入字节，指定 write_timeout 时超过期限仍未写完则报错并给出已写入的字节数
+fn write_port_bytes_timeout(
+    port_name: &str,
+    bytes_to_send: &[u8],
+    text: &str,
+    write_timeout: Option<Duration>,
+) -> Result<usize, String> {
+    let result = write_port_bytes_raw(port_name, bytes_to_send, text, write_timeout);
+    record_io_result(port_name, result.as_ref().err(), result.is_ok());
+    result
+}
+
+fn write_port_bytes_raw(
+    port_name: &str,
+    bytes_to_send: &[u8],
+    text: &str,
+    write_timeout: Option<Duration>,
+) -> Result<usize, String> {
+    check_not_paused(port_name)?;
+
+    // 虚拟串口逻辑
+    if port_name.starts_with("VIRTUAL-") {
+        let reply = {
+            let configs = VIRTUAL_CONFIGS.lock()
+                .expect("Failed to lock VIRTUAL_CONFIGS mutex");
+            let config = configs.get(port_name);
+            if config.and_then(|c| c.fault.as_deref()) == Some("drop_writes") {
+                // 模拟写入丢失：写入“成功”但设备收不到，不产生任何回应
+                Vec::new()
+            } else {
+                virtual_reply(port_name, config, bytes_to_send, text)
+            }
+        };
+
+        let mut buffers = VIRTUAL_BUFFERS.lock()
+            .expect("Failed to lock VIRTUAL_BUFFERS mutex");
+        let buffer = buffers
+            .get_mut(port_name)
+            .ok_or_else(|| format!("Virtual port {} not found", port_name))?;
+        buffer.extend_from_slice(&reply);
+        drop(buffers);
+        record_tx(port_name, bytes_to_send);
+
+        return Ok(bytes_to_send.len());
+    }
+    
+    // 真实串口逻辑
+    let port = port_writer(port_name)?;
+    let mut port = port.lock()
+        .expect("Failed to lock port mutex");
+
+    let result = match write_timeout {
+        Some(write_timeout) => write_with_deadline(&mut port, bytes_to_send, write_timeout),
+        None => port.write_all(bytes_to_send)
+            .map(|_| bytes_to_send.len())
+            .map_err(|e| (0, format!("Failed to write data: {}", e))),
+    };
+    drop(port);
+
+    let accepted = match &result {
+        Ok(n) | Err((n, _)) => *n,
+    };
+    record_tx(port_name, &bytes_to_send[..accepted]);
+    if let Err((_, e)) = result {
+        log_message(LogLevel::Error, format!("写入串口 {} 失败: {}", port_name, e));
+        return Err(e);
+    }
+    log_message(LogLevel::Debug, format!("向串口 {} 写入 {} 字节", port_name, bytes_to_send.len()));
+
+    Ok(bytes_to_send.len())
+}
+
+// 在期限内分段写入，返回已被端口接受的字节数；超时或出错时同时返回已接受的字节数
+fn write_with_deadline(
+    port: &mut Box<dyn SerialPort>,
+    bytes: &[u8],
+    write_timeout: Duration,
+) -> Result<usize, (usize, String)> {
+    let deadline = std::time::Instant::now() + write_timeout;
+    let original_timeout = port.timeout();
+    let mut written = 0;
+
+    let result = loop {
+        if written == bytes.len() {
+            break Ok(written);
+        }
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        if remaining.is_zero() {
+            break Err((written, format!(
+                "Timeout: write stalled after {} ms, {} of {} bytes accepted",
+                write_timeout.as_millis(),
+                written,
+                bytes.len()
+            )));
+        }
+        if let Err(e) = port.set_timeout(remaining) {
+            break Err((written, format!("Failed to set timeout: {}", e)));
+        }
+        match port.write(&bytes[written..]) {
+            Ok(n) => written += n,
+            Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut
+                || e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => break Err((written, format!("Failed to write data: {}", e))),
+        }
+    };
+
+    let _ = port.set_timeout(original_timeout);
+    result
+}
+
+// 内置演示虚拟串口，无需配置即可按名称打开
+const BUILTIN_VIRTUAL_PORTS: [&str; 3] = ["VIRTUAL-COM1", "VIRTUAL-COM2", "VIRTUAL-COM3"];
+
+// 端口列表中显示的虚拟串口类型
+fn virtual_port_label(mode: &str) -> String {
+    match mode {
+        "echo" => "Virtual Port (Echo)".to_string(),
+        "reply" => "Virtual Port (Reply)".to_string(),
+        "random" => "Virtual Port (Random)".to_string(),
+        "stream" => "Virtual Port (Stream)".to_string(),
+        other => format!("Virtual Port ({})", other),
+    }
+}
+
+// 虚拟串口的默认模式由端口名决定
+fn default_virtual_mode(port_name: &str) -> &'static str {
+    match port_name {
+        "VIRTUAL-COM1" => "echo",
+        "VIRTUAL-COM2" => "reply",
+        "VIRTUAL-COM3" => "random",
+        _ => "none",
+    }
+}
+
+// 计算虚拟串口收到数据后放入接收缓冲的内容
+fn virtual_reply(
+    port_name: &str,
+    config: Option<&VirtualPortConfig>,
+    bytes: &[u8],
+    text: &str,
+) -> Vec<u8> {
+    // 优先匹配自动应答表（精确匹配），有应答表但未命中时回显
+    if let Some(config) = config.filter(|config| !config.responses.is_empty()) {
+        return config.responses.iter()
+            .find(|entry| entry.request == bytes)
+            .map(|entry| entry.response.clone())
+            .unwrap_or_else(|| bytes.to_vec());
+    }
+
+    // 根据不同的虚拟串口模式执行不同操作
+    let mode = config
+        .and_then(|config| config.mode.as_deref())
+        .unwrap_or_else(|| default_virtual_mode(port_name));
+    match mode {
+        // Echo 模式：原样返回
+        "echo" => bytes.to_vec(),
+        // 变换回显：结果确定，便于测试解析器
+        "echo_upper" => bytes.to_ascii_uppercase(),
+        "echo_lower" => bytes.to_ascii_lowercase(),
+        "echo_reverse" => bytes.iter().rev().copied().collect(),
+        "echo_inc" => bytes.iter().map(|b| b.wrapping_add(1)).collect(),
+        "echo_rot13" => bytes.iter().map(|&b| rot13(b)).collect(),
+        // Reply 模式：返回固定回复
+        "reply" => format!("Received: {}", text).into_bytes(),
+        // Random 模式：返回随机数据
+        "random" => {
+            use std::time::{SystemTime, UNIX_EPOCH};
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis();
+            format!("Random-{}", timestamp % 10000).into_bytes()
+        }
+        _ => vec![],
+    }
+}
+
+// 计算写入 input 后虚拟串口会放入接收缓冲的字节，不修改任何状态；
+// random 模式结果不确定，直接报错；读故障（corrupt_1_percent 等）在读取时才生效，不计入
+#[tauri::command]
+fn virtual_expected_response(port_name: String, input: String, is_hex: bool) -> Result<Vec<u8>, String> {
+    if !port_name.starts_with("VIRTUAL-") {
+        return Err(format!("{} is not a virtual port", port_name));
+    }
+    if !VIRTUAL_BUFFERS.lock()
+        .expect("Failed to lock VIRTUAL_BUFFERS mutex")
+        .contains_key(&port_name)
+    {
+        return Err(format!("Virtual port {} not found", port_name));
+    }
+    let bytes = encode_payload(&input, is_hex, &WriteOptions::default())?;
+
+    let configs = VIRTUAL_CONFIGS.lock()
+        .expect("Failed to lock VIRTUAL_CONFIGS mutex");
+    let config = configs.get(&port_name);
+    if config.and_then(|c| c.fault.as_deref()) == Some("drop_writes") {
+        return Ok(Vec::new());
+    }
+    let has_responses = config.is_some_and(|c| !c.responses.is_empty());
+    let mode = config
+        .and_then(|config| config.mode.as_deref())
+        .unwrap_or_else(|| default_virtual_mode(&port_name));
+    if !has_responses && mode == "random" {
+        return Err(format!(
+            "Virtual port {} is in random mode, its response is not deterministic",
+            port_name
+        ));
+    }
+    Ok(virtual_reply(&port_name, config, &bytes, &input))
+}
+
+// ROT13 只变换 ASCII 字母，其余字节不变
+fn rot13(byte: u8) -> u8 {
+    match byte {
+        b'a'..=b'z' => (byte - b'a' + 13) % 26 + b'a',
+        b'A'..=b'Z' => (byte - b'A' + 13) % 26 + b'A',
+        _ => byte,
+    }
+}
+
+// 读写出错时记录为端口最近错误，成功传输数据时清除
+fn record_io_result(port_name: &str, error: Option<&String>, transferred: bool) {
+    if error.is_none() && !transferred {
+        return;
+    }
+    let mut errors = LAST_ERRORS.lock()
+        .expect("Failed to lock LAST_ERRORS mutex");
+    match error {
+        Some(message) => {
+            errors.insert(port_name.to_string(), LastError {
+                message: message.clone(),
+                timestamp_ms: unix_millis(),
+            });
+        }
+        None => {
+            errors.remove(port_name);
+        }
+    }
+}
+
+// 查询端口最近一次读写错误，之后没有成功读写时一直保留
+#[tauri::command]
+fn get_last_error(port_name: String) -> Option<LastError> {
+    LAST_ERRORS.lock()
+        .expect("Failed to lock LAST_ERRORS mutex")
+        .get(&port_name)
+        .cloned()
+}
+
+// 记录端口当前的读超时
+fn record_read_timeout(port_name: &str, timeout: Duration) {
+    READ_TIMEOUTS.lock()
+        .expect("Failed to lock READ_TIMEOUTS mutex")
+        .insert(port_name.to_string(), timeout.as_millis() as u64);
+}
+
+// 查询端口最近一次读取使用的读超时（毫秒，后台监听的短轮询超时不计入）；虚拟串口读取从不阻塞，返回 0
+#[tauri::command]
+fn get_read_timeout(port_name: String) -> Result<u64, String> {
+    if port_name.starts_with("VIRTUAL-") {
+        let buffers = VIRTUAL_BUFFERS.lock()
+            .expect("Failed to lock VIRTUAL_BUFFERS mutex");
+        if !buffers.contains_key(&port_name) {
+            return Err(format!("Virtual port {} not found", port_name));
+        }
+        return Ok(0);
+    }
+
+    READ_TIMEOUTS.lock()
+        .expect("Failed to lock READ_TIMEOUTS mutex")
+        .get(&port_name)
+        .copied()
+        .ok_or_else(|| format!("Port {} not found", port_name))
+}
+
+// 从串口读取一次数据（虚拟或真实），超时返回空数组
+fn read_port_bytes(port_name: &str, timeout_ms: u64) -> Result<Vec<u8>, String> {
+    read_port_bytes_max(port_name, timeout_ms, usize::MAX)
+}
+
+// 从串口读取一次数据，最多 max_len 字节，多余的数据留在缓冲中
+fn read_port_bytes_max(port_name: &str, timeout_ms: u64, max_len: usize) -> Result<Vec<u8>, String> {
+    read_port_bytes_with(port_name, timeout_ms, max_len, true)
+}
+
+// 后台读取线程使用的短轮询读取：不记录为用户的读超时，监听停止后恢复用户超时
+fn read_port_bytes_poll(port_name: &str, timeout_ms: u64, max_len: usize) -> Result<Vec<u8>, String> {
+    read_port_bytes_with(port_name, timeout_ms, max_len, false)
+}
+
+// 读取并记录最近错误；超时空读不算成功，不清除之前的错误
+fn read_port_bytes_with(
+    port_name: &str,
+    timeout_ms: u64,
+    max_len: usize,
+    user_timeout: bool,
+) -> Result<Vec<u8>, String> {
+    let result = read_port_bytes_raw(port_name, timeout_ms, max_len, user_timeout);
+    record_io_result(
+        port_name,
+        result.as_ref().err(),
+        result.as_ref().is_ok_and(|data| !data.is_empty()),
+    );
+    result
+}
+
+fn read_port_bytes_raw(
+    port_name: &str,
+    timeout_ms: u64,
+    max_len: usize,
+    user_timeout: bool,
+) -> Result<Vec<u8>, String> {
+    check_not_paused(port_name)?;
+
+    // 虚拟串口逻辑
+    if port_name.starts_with("VIRTUAL-") {
+        let fault = take_virtual_read_fault(port_name);
+        match fault.as_deref() {
+            Some("disconnect_on_next_read") => {
+                // 模拟设备拔出：移除缓冲，后续读写均失败，直到重新打开
+                log_message(LogLevel::Warning, format!("虚拟串口 {} 模拟断开", port_name));
+                VIRTUAL_BUFFERS.lock()
+                    .expect("Failed to lock VIRTUAL_BUFFERS mutex")
+                    .remove(port_name);
+                stop_virtual_stream(port_name);
+                return Err(format!("Virtual port {} disconnected (simulated fault)", port_name));
+            }
+            Some("timeout_reads") => {
+                // 模拟设备无响应：等满超时后返回空数据
+                std::thread::sleep(Duration::from_millis(timeout_ms));
+                return Ok(vec![]);
+            }
+            _ => {}
+        }
+
+        let mut buffers = VIRTUAL_BUFFERS.lock()
+            .expect("Failed to lock VIRTUAL_BUFFERS mutex");
+        
+        if let Some(buffer) = buffers.get_mut(port_name) {
+            if buffer.is_empty() {
+                return Ok(vec![]);
+            }
+            
+            // 读取缓冲数据（不超过 max_len）
+            let mut data = if buffer.len() <= max_len {
+                std::mem::take(buffer)
+            } else {
+                buffer.drain(..max_len).collect()
+            };
+            drop(buffers);
+            if fault.as_deref() == Some("corrupt_1_percent") {
+                corrupt_bytes(&mut data);
+            }
+            record_rx(port_name, &data);
+            return Ok(data);
+        } else {
+            return Err(format!("Virtual port {} not found", port_name));
+        }
+    }
+    
+    // 先返回测试注入的数据
+    #[cfg(feature = "test-inject")]
+    {
+        let mut injected = INJECTED_READS.lock()
+            .expect("Failed to lock INJECTED_READS mutex");
+        if let Some(pending) = injected.get_mut(port_name).filter(|p| !p.is_empty()) {
+            let data: Vec<u8> = pending.drain(..pending.len().min(max_len)).collect();
+            drop(injected);
+            record_rx(port_name, &data);
+            return Ok(data);
+        }
+    }
+
+    // 真实串口逻辑
+    let port = port_reader(port_name)?;
+    let mut port = port.lock()
+        .expect("Failed to lock port mutex");
+
+    // 设置超时
+    port.set_timeout(Duration::from_millis(timeout_ms))
+        .map_err(|e| format!("Failed to set timeout: {}", e))?;
+    if user_timeout {
+        record_read_timeout(port_name, Duration::from_millis(timeout_ms));
+    }
+
+    // 复用端口的读缓冲：每次调用不再分配并清零 1KB，
+    // 只按实际读到的字节数拷贝返回，超时空读不产生任何分配。
+    // 读取期间把缓冲取出，避免全局锁阻塞其它端口的读取
+    let mut buffer = READ_SCRATCH.lock()
+        .expect("Failed to lock READ_SCRATCH mutex")
+        .remove(port_name)
+        .unwrap_or_else(|| vec![0; READ_CHUNK_SIZE]);
+
+    // 调用方要求更大的单次读取时（如高波特率监听）扩大缓冲，最多 MAX_READ_CHUNK_SIZE
+    if max_len != usize::MAX && buffer.len() < max_len.min(MAX_READ_CHUNK_SIZE) {
+        buffer.resize(max_len.min(MAX_READ_CHUNK_SIZE), 0);
+    }
+    let read_len = buffer.len().min(max_len);
+    let mut retries = 0;
+    let result = loop {
+        match port.read(&mut buffer[..read_len]) {
+            Ok(n) => {
+                record_rx(port_name, &buffer[..n]);
+                break Ok(buffer[..n].to_vec());
+            }
+            Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => {
+                break Ok(vec![]);
+            }
+            // 被信号中断（EINTR）或暂时无数据（EAGAIN）属于瞬时错误，有限次重试
+            Err(ref e) if retries < READ_RETRY_LIMIT
+                && matches!(e.kind(), std::io::ErrorKind::Interrupted | std::io::ErrorKind::WouldBlock) =>
+            {
+                retries += 1;
+                if e.kind() == std::io::ErrorKind::WouldBlock {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+            }
+            Err(e) => {
+                log_message(LogLevel::Error, format!("读取串口 {} 失败: {}", port_name, e));
+                break Err(format!("Failed to read data: {}", e));
+            }
+        }
+    };
+
+    READ_SCRATCH.lock()
+        .expect("Failed to lock READ_SCRATCH mutex")
+        .insert(port_name.to_string(), buffer);
+    result
+}
+
+// 取出虚拟串口当前的读故障；disconnect_on_next_read 只触发一次
+fn take_virtual_read_fault(port_name: &str) -> Option<String> {
+    let mut configs = VIRTUAL_CONFIGS.lock()
+        .expect("Failed to lock VIRTUAL_CONFIGS mutex");
+    let config = configs.get_mut(port_name)?;
+    if config.fault.as_deref() == Some("disconnect_on_next_read") {
+        return config.fault.take();
+    }
+    config.fault.clone()
+}
+
+// 按约 1% 的概率随机翻转每个字节中的一位
+fn corrupt_bytes(data: &mut [u8]) {
+    let random = pseudo_random_bytes(data.len() * 3);
+    for (byte, r) in data.iter_mut().zip(random.chunks_exact(3)) {
+        let roll = u16::from_le_bytes([r[0], r[1]]);
+        if roll < 656 {
+            *byte ^= 1 << (r[2] % 8);
+        }
+    }
+}
+
+// 为虚拟串口注入故障，用于测试错误处理（"none" 清除故障）
+#[tauri::command]
+fn set_virtual_fault(port_name: String, fault: String) -> Result<String, String> {
+    if !port_name.starts_with("VIRTUAL-") {
+        return Err(format!("Port {} is not a virtual port", port_name));
+    }
+    let fault = match fault.as_str() {
+        "none" => None,
+        "disconnect_on_next_read" | "corrupt_1_percent" | "drop_writes" | "timeout_reads" => {
+            Some(fault)
+        }
+        other => return Err(format!("Unsupported virtual fault: {}", other)),
+    };
+
+    let mut configs = VIRTUAL_CONFIGS.lock()
+        .expect("Failed to lock VIRTUAL_CONFIGS mutex");
+    configs.entry(port_name.clone()).or_default().fault = fault.clone();
+
+    Ok(match fault {
+        Some(fault) => format!("Virtual port {} fault set to {}", port_name, fault),
+        None => format!("Virtual port {} fault cleared", port_name),
+    })
+}
+
+// 虚拟串口场景文件格式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VirtualScenario {
+    version: u32,
+    ports: HashMap<String, VirtualPortConfig>,
+}
+
+const VIRTUAL_SCENARIO_VERSION: u32 = 1;
+
+// 把所有虚拟串口配置（模式、应答表、故障、推送数据）导出为 JSON 场景文件
+#[tauri::command]
+fn export_virtual_scenario(file_path: String) -> Result<String, String> {
+    let scenario = VirtualScenario {
+        version: VIRTUAL_SCENARIO_VERSION,
+        ports: VIRTUAL_CONFIGS.lock()
+            .expect("Failed to lock VIRTUAL_CONFIGS mutex")
+            .clone(),
+    };
+    let json = serde_json::to_string_pretty(&scenario)
+        .map_err(|e| format!("Failed to serialize scenario: {}", e))?;
+    std::fs::write(&file_path, json)
+        .map_err(|e| format!("Failed to write {}: {}", file_path, e))?;
+
+    Ok(format!("Exported {} virtual ports to {}", scenario.ports.len(), file_path))
+}
+
+// 从场景文件恢复虚拟串口配置；同名端口的配置被覆盖，已打开的端口立即按新配置运行
+#[tauri::command]
+fn import_virtual_scenario(file_path: String) -> Result<String, String> {
+    let content = std::fs::read_to_string(&file_path)
+        .map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+    let scenario: VirtualScenario = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid scenario file: {}", e))?;
+    if scenario.version != VIRTUAL_SCENARIO_VERSION {
+        return Err(format!("Unsupported scenario version: {}", scenario.version));
+    }
+    if let Some(name) = scenario.ports.keys().find(|name| !name.starts_with("VIRTUAL-")) {
+        return Err(format!("Port {} is not a virtual port", name));
+    }
+
+    let names: Vec<String> = scenario.ports.keys().cloned().collect();
+    VIRTUAL_CONFIGS.lock()
+        .expect("Failed to lock VIRTUAL_CONFIGS mutex")
+        .extend(scenario.ports);
+
+    // 模式可能变化，重启（或停止）推送线程
+    for name in &names {
+        stop_virtual_stream(name);
+        start_virtual_stream_if_needed(name);
+    }
+
+    Ok(format!("Imported {} virtual ports from {}", names.len(), file_path))
+}
+
+// 设置虚拟串口工作模式；stream 模式按 interval_ms 定时把 payload 推入接收缓冲
+#[tauri::command]
+fn set_virtual_mode(
+    port_name: String,
+    mode: String,
+    stream_payload: Option<Vec<u8>>,
+    stream_interval_ms: Option<u64>,
+) -> Result<String, String> {
+    if !port_name.starts_with("VIRTUAL-") {
+        return Err(format!("Port {} is not a virtual port", port_name));
+    }
+    match mode.as_str() {
+        "echo" | "reply" | "random" | "none" => {}
+        "echo_upper" | "echo_lower" | "echo_reverse" | "echo_inc" | "echo_rot13" => {}
+        "stream" => {
+            if stream_payload.as_ref().is_none_or(|p| p.is_empty()) {
+                return Err("Stream mode requires a non-empty payload".to_string());
+            }
+        }
+        other => return Err(format!("Unsupported virtual mode: {}", other)),
+    }
+
+    {
+        let mut configs = VIRTUAL_CONFIGS.lock()
+            .expect("Failed to lock VIRTUAL_CONFIGS mutex");
+        let config = configs.entry(port_name.clone()).or_default();
+        config.mode = Some(mode.clone());
+        if let Some(payload) = stream_payload {
+            config.stream_payload = payload;
+        }
+        if stream_interval_ms.is_some() {
+            config.stream_interval_ms = stream_interval_ms;
+        }
+    }
+
+    // 模式切换后重启（或停止）推送线程
+    stop_virtual_stream(&port_name);
+    start_virtual_stream_if_needed(&port_name);
+
+    Ok(format!("Virtual port {} set to {} mode", port_name, mode))
+}
+
+// 端口已打开且处于 stream 模式时启动推送线程
+fn start_virtual_stream_if_needed(port_name: &str) {
+    let (payload, interval_ms) = {
+        let configs = VIRTUAL_CONFIGS.lock()
+            .expect("Failed to lock VIRTUAL_CONFIGS mutex");
+        match configs.get(port_name) {
+            Some(config) if config.mode.as_deref() == Some("stream") => (
+                config.stream_payload.clone(),
+                config.stream_interval_ms.unwrap_or(1000).max(1),
+            ),
+            _ => return,
+        }
+    };
+    if !VIRTUAL_BUFFERS.lock()
+        .expect("Failed to lock VIRTUAL_BUFFERS mutex")
+        .contains_key(port_name)
+    {
+        return;
+    }
+
+    let mut streams = VIRTUAL_STREAMS.lock()
+        .expect("Failed to lock VIRTUAL_STREAMS mutex");
+    if streams.contains_key(port_name) {
+        return;
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let stop = stop.clone();
+        let port_name = port_name.to_string();
+        std::thread::spawn(move || {
+            let interval = Duration::from_millis(interval_ms);
+            let mut next_tick = std::time::Instant::now() + interval;
+            while !stop.load(Ordering::Relaxed) {
+                // 分段休眠，以便及时响应停止信号
+                let now = std::time::Instant::now();
+                if now < next_tick {
+                    std::thread::sleep((next_tick - now).min(Duration::from_millis(20)));
+                    continue;
+                }
+                next_tick += interval;
+
+                let mut buffers = VIRTUAL_BUFFERS.lock()
+                    .expect("Failed to lock VIRTUAL_BUFFERS mutex");
+                match buffers.get_mut(&port_name) {
+                    Some(buffer) => buffer.extend_from_slice(&payload),
+                    None => break,
+                }
+            }
+        })
+    };
+    streams.insert(port_name.to_string(), ListenerHandle { stop, thread, task: None });
+}
+
+// 停止虚拟串口推送线程
+fn stop_virtual_stream(port_name: &str) {
+    let handle = VIRTUAL_STREAMS.lock()
+        .expect("Failed to lock VIRTUAL_STREAMS mutex")
+        .remove(port_name);
+    if let Some(handle) = handle {
+        handle.stop.store(true, Ordering::Relaxed);
+        let _ = handle.thread.join();
+    }
+}
+
+// 把按端口名登记的条目迁移到新名称下
+fn rename_key<V>(map: &mut HashMap<String, V>, old_name: &str, new_name: &str) {
+    if let Some(value) = map.remove(old_name) {
+        map.insert(new_name.to_string(), value);
+    }
+}
+
+// 重命名虚拟串口，缓冲数据、配置、会话以及统计、捕获、暂停状态等附属状态一并迁移；
+// 按名称运行的后台任务（监听、推送、自动关闭、状态线记录）需先停止
+#[tauri::command]
+fn rename_virtual_port(old_name: String, new_name: String) -> Result<String, String> {
+    if !old_name.starts_with("VIRTUAL-") || !new_name.starts_with("VIRTUAL-") {
+        return Err("Both names must be virtual ports (VIRTUAL-*)".to_string());
+    }
+    if old_name == new_name {
+        return Err("New name is the same as the old name".to_string());
+    }
+    if LISTENERS.lock()
+        .expect("Failed to lock LISTENERS mutex")
+        .contains_key(&old_name)
+    {
+        return Err(format!("Stop the listener on {} before renaming", old_name));
+    }
+    if VIRTUAL_STREAMS.lock()
+        .expect("Failed to lock VIRTUAL_STREAMS mutex")
+        .contains_key(&old_name)
+    {
+        return Err(format!("Stop the stream on {} before renaming", old_name));
+    }
+    if AUTO_CLOSE.lock()
+        .expect("Failed to lock AUTO_CLOSE mutex")
+        .contains_key(&old_name)
+    {
+        return Err(format!("Disable auto-close on {} before renaming", old_name));
+    }
+    if SIGNAL_LOGS.lock()
+        .expect("Failed to lock SIGNAL_LOGS mutex")
+        .contains_key(&old_name)
+    {
+        return Err(format!("Stop the signal log on {} before renaming", old_name));
+    }
+
+    // 同时持有缓冲区和配置的锁，保证迁移是原子的
+    let mut buffers = VIRTUAL_BUFFERS.lock()
+        .expect("Failed to lock VIRTUAL_BUFFERS mutex");
+    let mut configs = VIRTUAL_CONFIGS.lock()
+        .expect("Failed to lock VIRTUAL_CONFIGS mutex");
+
+    if buffers.contains_key(&new_name) || configs.contains_key(&new_name) {
+        return Err(format!("Virtual port {} already exists", new_name));
+    }
+    if !buffers.contains_key(&old_name) && !configs.contains_key(&old_name) {
+        return Err(format!("Virtual port {} not found", old_name));
+    }
+
+    if let Some(buffer) = buffers.remove(&old_name) {
+        buffers.insert(new_name.clone(), buffer);
+    }
+    // 内置端口的模式由名称决定，改名后需显式保留
+    let mut config = configs.remove(&old_name).unwrap_or_default();
+    if config.mode.is_none() {
+        config.mode = Some(default_virtual_mode(&old_name).to_string());
+    }
+    configs.insert(new_name.clone(), config);
+
+    rename_key(&mut PORT_SESSIONS.lock()
+        .expect("Failed to lock PORT_SESSIONS mutex"), &old_name, &new_name);
+
+    // 附属状态在持有缓冲区锁期间迁移，期间端口不会被打开或关闭
+    rename_key(&mut PORT_STATS.lock()
+        .expect("Failed to lock PORT_STATS mutex"), &old_name, &new_name);
+    rename_key(&mut CAPTURES.lock()
+        .expect("Failed to lock CAPTURES mutex"), &old_name, &new_name);
+    rename_key(&mut LAST_ERRORS.lock()
+        .expect("Failed to lock LAST_ERRORS mutex"), &old_name, &new_name);
+    rename_key(&mut PORT_CONFIGS.lock()
+        .expect("Failed to lock PORT_CONFIGS mutex"), &old_name, &new_name);
+    rename_key(&mut AUTO_RECONNECT.lock()
+        .expect("Failed to lock AUTO_RECONNECT mutex"), &old_name, &new_name);
+    rename_key(&mut TERMINAL_LINES.lock()
+        .expect("Failed to lock TERMINAL_LINES mutex"), &old_name, &new_name);
+    rename_key(&mut FRAME_PARSERS.lock()
+        .expect("Failed to lock FRAME_PARSERS mutex"), &old_name, &new_name);
+    rename_key(&mut STREAM_CHECKSUMS.lock()
+        .expect("Failed to lock STREAM_CHECKSUMS mutex"), &old_name, &new_name);
+    rename_key(&mut DETECTED_ENCODINGS.lock()
+        .expect("Failed to lock DETECTED_ENCODINGS mutex"), &old_name, &new_name);
+    rename_key(&mut SAMPLE_REMAINDERS.lock()
+        .expect("Failed to lock SAMPLE_REMAINDERS mutex"), &old_name, &new_name);
+    #[cfg(feature = "test-inject")]
+    rename_key(&mut INJECTED_READS.lock()
+        .expect("Failed to lock INJECTED_READS mutex"), &old_name, &new_name);
+    let mut paused = PAUSED_PORTS.lock()
+        .expect("Failed to lock PAUSED_PORTS mutex");
+    if paused.remove(&old_name) {
+        paused.insert(new_name.clone());
+    }
+
+    Ok(format!("Virtual port {} renamed to {}", old_name, new_name))
+}
+
+// 读取数据并以 base64 字符串返回，减少大数据量时的桥接开销
+#[tauri::command]
+fn read_serial_base64(
+    port_name: String,
+    session_id: String,
+    timeout_ms: Option<u64>,
+) -> Result<String, String> {
+    use base64::Engine;
+
+    check_session(&port_name, &session_id)?;
+    let timeout_ms = resolve_read_timeout(timeout_ms);
+    let data = read_port_bytes(&port_name, timeout_ms)?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(data))
+}
+
+// 发送 base64 编码的二进制数据
+#[tauri::command]
+fn write_serial_base64(
+    port_name: String,
+    session_id: String,
+    data: String,
+) -> Result<String, String> {
+    use base64::Engine;
+
+    check_session(&port_name, &session_id)?;
+    let bytes_to_send = base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .map_err(|e| format!("Invalid base64 string: {}", e))?;
+
+    if bytes_to_send.is_empty() {
+        return Err("nothing to send".to_string());
+    }
+
+    let text = String::from_utf8_lossy(&bytes_to_send).into_owned();
+    let sent = write_port_bytes(&port_name, &bytes_to_send, &text)?;
+    Ok(format!("Sent {} bytes", sent))
+}
+
+// 串口当前参数（从句柄回读）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PortSettings {
+    port_name: String,
+    baud_rate: u32,
+    data_bits: u8,
+    stop_bits: u8,
+    parity: String,
+    flow_control: String,
+}
+
+// 从串口句柄回读当前参数
+fn read_port_settings(port_name: &str, port: &dyn SerialPort) -> Result<PortSettings, String> {
+    let map_err = |e: serialport::Error| format!("Failed to read port settings: {}", e);
+
+    let data_bits = match port.data_bits().map_err(map_err)? {
+        serialport::DataBits::Five => 5,
+        serialport::DataBits::Six => 6,
+        serialport::DataBits::Seven => 7,
+        serialport::DataBits::Eight => 8,
+    };
+    let stop_bits = match port.stop_bits().map_err(map_err)? {
+        serialport::StopBits::One => 1,
+        serialport::StopBits::Two => 2,
+    };
+    let parity = match port.parity().map_err(map_err)? {
+        serialport::Parity::None => "None",
+        serialport::Parity::Odd => "Odd",
+        serialport::Parity::Even => "Even",
+    };
+    let flow_control = match port.flow_control().map_err(map_err)? {
+        serialport::FlowControl::None => "None",
+        serialport::FlowControl::Software => "Software",
+        serialport::FlowControl::Hardware => "Hardware",
+    };
+
+    Ok(PortSettings {
+        port_name: port_name.to_string(),
+        baud_rate: port.baud_rate().map_err(map_err)?,
+        data_bits,
+        stop_bits,
+        parity: parity.to_string(),
+        flow_control: flow_control.to_string(),
+    })
+}
+
+// 将串口恢复为 115200 8N1、无流控并清空收发缓冲，返回回读的参数
+#[tauri::command]
+fn reset_port_defaults(port_name: String, session_id: String) -> Result<PortSettings, String> {
+    check_session(&port_name, &session_id)?;
+
+    // 虚拟串口没有线路参数，只清空缓冲
+    if port_name.starts_with("VIRTUAL-") {
+        discard_pending_input(&port_name)?;
+        return Ok(PortSettings {
+            port_name,
+            baud_rate: 115200,
+            data_bits: 8,
+            stop_bits: 1,
+            parity: "None".to_string(),
+            flow_control: "None".to_string(),
+        });
+    }
+
+    let port = port_writer(&port_name)?;
+    let mut port = port.lock()
+        .expect("Failed to lock port mutex");
+
+    let map_err = |e: serialport::Error| format!("Failed to reset port: {}", e);
+    port.set_baud_rate(115200).map_err(map_err)?;
+    port.set_data_bits(serialport::DataBits::Eight).map_err(map_err)?;
+    port.set_stop_bits(serialport::StopBits::One).map_err(map_err)?;
+    port.set_parity(serialport::Parity::None).map_err(map_err)?;
+    port.set_flow_control(serialport::FlowControl::None).map_err(map_err)?;
+    port.clear(serialport::ClearBuffer::All).map_err(map_err)?;
+
+    read_port_settings(&port_name, port.as_ref())
+}
+
+// 解析行尾符配置
+fn line_ending_bytes(line_ending: &str) -> Result<&'static [u8], String> {
+    match line_ending.to_lowercase().as_str() {
+        "none" | "" => Ok(b""),
+        "cr" => Ok(b"\r"),
+        "lf" => Ok(b"\n"),
+        "crlf" => Ok(b"\r\n"),
+        other => Err(format!("Unsupported line ending: {}", other)),
+    }
+}
+
+// 终端发送整行时推送的事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TerminalLineEvent {
+    port_name: String,
+    line: String,
+}
+
+// 终端按键输入：后端维护行缓冲，处理退格，回车时带行尾发送整行
+// 返回当前正在编辑的行内容
+#[tauri::command]
+fn terminal_feed(
+    window: tauri::Window,
+    port_name: String,
+    session_id: String,
+    ch: char,
+    line_ending: Option<String>,
+) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+    let completed_line = {
+        let mut lines = TERMINAL_LINES.lock()
+            .expect("Failed to lock TERMINAL_LINES mutex");
+        let line = lines.entry(port_name.clone()).or_default();
+
+        match ch {
+            // 退格（BS / DEL）
+            '\u{08}' | '\u{7f}' => {
+                line.pop();
+                None
+            }
+            '\r' | '\n' => Some(std::mem::take(line)),
+            c if !c.is_control() => {
+                line.push(c);
+                None
+            }
+            // 其它控制字符忽略
+            _ => None,
+        }
+    };
+
+    match completed_line {
+        Some(line) => {
+            let ending = line_ending_bytes(line_ending.as_deref().unwrap_or("crlf"))?;
+            let mut bytes_to_send = line.as_bytes().to_vec();
+            bytes_to_send.extend_from_slice(ending);
+            write_port_bytes(&port_name, &bytes_to_send, &line)?;
+
+            window.emit("terminal-line", TerminalLineEvent {
+                port_name: port_name.clone(),
+                line,
+            }).map_err(|e| format!("Failed to emit event: {}", e))?;
+
+            Ok(String::new())
+        }
+        None => {
+            let lines = TERMINAL_LINES.lock()
+                .expect("Failed to lock TERMINAL_LINES mutex");
+            Ok(lines.get(&port_name).cloned().unwrap_or_default())
+        }
+    }
+}
+
+// 设置虚拟串口的自动应答表，传入空列表表示清除
+#[tauri::command]
+fn set_virtual_responses(
+    port_name: String,
+    pairs: Vec<VirtualResponse>,
+) -> Result<String, String> {
+    if !port_name.starts_with("VIRTUAL-") {
+        return Err(format!("Port {} is not a virtual port", port_name));
+    }
+
+    let count = pairs.len();
+    let mut configs = VIRTUAL_CONFIGS.lock()
+        .expect("Failed to lock VIRTUAL_CONFIGS mutex");
+    configs.entry(port_name.clone()).or_default().responses = pairs;
+
+    Ok(format!("Registered {} responses for {}", count, port_name))
+}
+
+// 在截止时间前读取一次数据，无数据时返回空数组
+fn read_port_chunk(port_name: &str, deadline: std::time::Instant) -> Result<Vec<u8>, String> {
+    read_port_chunk_max(port_name, deadline, usize::MAX)
+}
+
+// 在截止时间前读取一次数据，最多 max_len 字节
+fn read_port_chunk_max(
+    port_name: &str,
+    deadline: std::time::Instant,
+    max_len: usize,
+) -> Result<Vec<u8>, String> {
+    let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+    let data = read_port_bytes_max(port_name, remaining.as_millis().max(1) as u64, max_len)?;
+
+    // 虚拟串口读取不阻塞，稍作等待避免空转
+    if data.is_empty() && port_name.starts_with("VIRTUAL-") {
+        std::thread::sleep(remaining.min(Duration::from_millis(1)));
+    }
+    Ok(data)
+}
+
+// 按编码名称（如 utf-8、gbk）解码字节，不可解码的字节以替换字符显示
+fn decode_bytes(bytes: &[u8], encoding: &str) -> Result<String, String> {
+    let encoding = encoding_rs::Encoding::for_label(encoding.trim().as_bytes())
+        .ok_or_else(|| format!("Unsupported encoding: {}", encoding))?;
+    let (text, _, _) = encoding.decode(bytes);
+    Ok(text.into_owned())
+}
+
+// 等待接收数据中出现指定字符串，返回已接收的全部文本
+#[tauri::command(async)]
+fn expect_string(
+    port_name: String,
+    session_id: String,
+    needle: String,
+    encoding: Option<String>,
+    timeout_ms: Option<u64>,
+) -> Result<String, String> {
+    use std::time::Instant;
+
+    check_session(&port_name, &session_id)?;
+    let timeout_ms = resolve_read_timeout(timeout_ms);
+    let encoding = encoding.unwrap_or_else(|| "utf-8".to_string());
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    let mut received: Vec<u8> = Vec::new();
+
+    loop {
+        let data = read_port_chunk(&port_name, deadline)?;
+        if !data.is_empty() {
+            received.extend_from_slice(&data);
+            // 每次整体解码，避免多字节字符被拆分在两次读取之间
+            let text = decode_bytes(&received, &encoding)?;
+            if text.contains(&needle) {
+                return Ok(text);
+            }
+        }
+        if Instant::now() >= deadline {
+            let text = decode_bytes(&received, &encoding)?;
+            return Err(format!(
+                "Timed out waiting for {:?}, received: {}",
+                needle, text
+            ));
+        }
+    }
+}
+
+// 正则匹配读取结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RegexReadResult {
+    text: String,
+    matched: bool,
+}
+
+// 读取直到解码后的文本匹配正则（例如 shell 提示符 \$\s*$），超时返回已收到的内容
+#[tauri::command(async)]
+fn read_until_regex(
+    port_name: String,
+    session_id: String,
+    pattern: String,
+    encoding: Option<String>,
+    timeout_ms: Option<u64>,
+) -> Result<RegexReadResult, String> {
+    use std::time::Instant;
+
+    check_session(&port_name, &session_id)?;
+    let timeout_ms = resolve_read_timeout(timeout_ms);
+    // 先校验正则，避免读取后才报错
+    let regex = regex::Regex::new(&pattern)
+        .map_err(|e| format!("Invalid pattern: {}", e))?;
+    let encoding = encoding.unwrap_or_else(|| "utf-8".to_string());
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    let mut received: Vec<u8> = Vec::new();
+
+    loop {
+        let data = read_port_chunk(&port_name, deadline)?;
+        if !data.is_empty() {
+            received.extend_from_slice(&data);
+            let text = decode_bytes(&received, &encoding)?;
+            if regex.is_match(&text) {
+                return Ok(RegexReadResult { text, matched: true });
+            }
+        }
+        if Instant::now() >= deadline {
+            return Ok(RegexReadResult {
+                text: decode_bytes(&received, &encoding)?,
+                matched: false,
+            });
+        }
+    }
+}
+
+// AT 命令响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AtResponse {
+    lines: Vec<String>,
+    result_code: String,
+}
+
+// 判断是否为 AT 最终结果码
+fn is_at_final_result(line: &str) -> bool {
+    line == "OK"
+        || line == "ERROR"
+        || line.starts_with("+CME ERROR")
+        || line.starts_with("+CMS ERROR")
+}
+
+// 发送 AT 命令（自动追加 \r），读取直到出现最终结果码
+#[tauri::command(async)]
+fn at_command(
+    port_name: String,
+    session_id: String,
+    command: String,
+    timeout_ms: Option<u64>,
+) -> Result<AtResponse, String> {
+    use std::time::Instant;
+
+    check_session(&port_name, &session_id)?;
+    let timeout_ms = resolve_read_timeout(timeout_ms);
+    let command = command.trim_end().to_string();
+    let mut bytes_to_send = command.as_bytes().to_vec();
+    bytes_to_send.push(b'\r');
+
+    discard_pending_input(&port_name)?;
+    write_port_bytes(&port_name, &bytes_to_send, &command)?;
+
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    let mut received: Vec<u8> = Vec::new();
+
+    loop {
+        let data = read_port_chunk(&port_name, deadline)?;
+        if !data.is_empty() {
+            received.extend_from_slice(&data);
+
+            let text = String::from_utf8_lossy(&received);
+            let mut lines: Vec<String> = text
+                .split(['\r', '\n'])
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect();
+
+            if let Some(pos) = lines.iter().position(|line| is_at_final_result(line)) {
+                let result_code = lines[pos].clone();
+                lines.truncate(pos);
+                // 去掉模块回显的命令本身
+                if lines.first() == Some(&command) {
+                    lines.remove(0);
+                }
+                return Ok(AtResponse { lines, result_code });
+            }
+        }
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "Timed out waiting for AT result code, received: {}",
+                String::from_utf8_lossy(&received)
+            ));
+        }
+    }
+}
+
+// 端口暂停期间监听线程检查恢复的间隔
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+// 监听线程单次读取的超时，保持较短以便及时响应停止信号
+const LISTENER_READ_TIMEOUT_MS: u64 = 10;
+
+// 测试用：向已打开的真实串口的读路径注入数据，后续读取（含监听线程）先取出这些字节再读硬件
+// 仅在启用 test-inject 特性时编译，发布版本不包含
+#[cfg(feature = "test-inject")]
+#[tauri::command]
+fn inject_read(port_name: String, bytes: Vec<u8>) -> Result<String, String> {
+    if port_name.starts_with("VIRTUAL-") {
+        return Err(format!("Port {} is virtual, write to it instead of injecting", port_name));
+    }
+    if !SERIAL_PORTS.lock()
+        .expect("Failed to lock SERIAL_PORTS mutex")
+        .contains_key(&port_name)
+    {
+        return Err(format!("Port {} not found", port_name));
+    }
+
+    let count = bytes.len();
+    INJECTED_READS.lock()
+        .expect("Failed to lock INJECTED_READS mutex")
+        .entry(port_name.clone())
+        .or_default()
+        .extend(bytes);
+    Ok(format!("Injected {} bytes into {}", count, port_name))
+}
+
+// 推荐单次读取的缓冲大小：覆盖约 20ms 内到达的数据（按 10 位/字节），
+// 且至少容纳一个完整帧；取 2 的幂并限制在 64~16384 字节
+fn suggest_read_chunk_size(baud_rate: u32, expected_frame_bytes: usize) -> usize {
+    let bytes_per_window = baud_rate as usize / 10 / 50;
+    bytes_per_window
+        .max(expected_frame_bytes)
+        .next_power_of_two()
+        .clamp(MIN_READ_CHUNK_SIZE, MAX_READ_CHUNK_SIZE)
+}
+
+// 按波特率和预期帧长推荐读缓冲大小，兼顾延迟与系统调用次数
+#[tauri::command]
+fn suggest_read_chunk(baud_rate: u32, expected_frame_bytes: usize) -> Result<usize, String> {
+    if baud_rate == 0 {
+        return Err("Baud rate must be greater than 0".to_string());
+    }
+    Ok(suggest_read_chunk_size(baud_rate, expected_frame_bytes))
+}
+
+// 启动后台监听线程，收到数据时推送 serial-data 事件
+// 空闲时按指数退避休眠，最长 poll_interval_ms；有数据时立即继续读取
+// 未指定 read_chunk_size 时按打开时的波特率自动推荐
+#[tauri::command]
+fn start_serial_listener(
+    window: tauri::Window,
+    port_name: String,
+    session_id: String,
+    poll_interval_ms: Option<u64>,
+    read_chunk_size: Option<usize>,
+) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+    let poll_interval_ms = poll_interval_ms.unwrap_or(50).max(1);
+    let read_chunk_size = match read_chunk_size {
+        Some(size) => size.clamp(1, MAX_READ_CHUNK_SIZE),
+        None => PORT_CONFIGS.lock()
+            .expect("Failed to lock PORT_CONFIGS mutex")
+            .get(&port_name)
+            .filter(|config| config.baud_rate > 0 && !config.port_name.starts_with("VIRTUAL-"))
+            .map(|config| suggest_read_chunk_size(config.baud_rate, 0))
+            .unwrap_or(READ_CHUNK_SIZE),
+    };
+
+    spawn_port_reader(&port_name, move |port_name, stop| {
+        run_listener(window.clone(), port_name, poll_interval_ms, read_chunk_size, stop);
+    })?;
+
+    Ok(format!("Listener for {} started", port_name))
+}
+
+// 为端口启动后台读取线程并登记到 LISTENERS，同一端口同时只允许一个
+fn spawn_port_reader(
+    port_name: &str,
+    task: impl Fn(String, Arc<AtomicBool>) + Send + Sync + 'static,
+) -> Result<(), String> {
+    // 确认端口已打开
+    if port_name.starts_with("VIRTUAL-") {
+        let buffers = VIRTUAL_BUFFERS.lock()
+            .expect("Failed to lock VIRTUAL_BUFFERS mutex");
+        if !buffers.contains_key(port_name) {
+            return Err(format!("Virtual port {} not found", port_name));
+        }
+    } else {
+        let ports = SERIAL_PORTS.lock()
+            .expect("Failed to lock SERIAL_PORTS mutex");
+        if !ports.contains_key(port_name) {
+            return Err(format!("Port {} not found", port_name));
+        }
+    }
+
+    register_listener(port_name, Arc::new(task))
+}
+
+// 启动读取线程并登记到 LISTENERS；线程退出时统一恢复用户读超时
+fn register_listener(port_name: &str, task: ListenerTask) -> Result<(), String> {
+    let mut listeners = LISTENERS.lock()
+        .expect("Failed to lock LISTENERS mutex");
+    if listeners.contains_key(port_name) {
+        return Err(format!("Listener for {} is already running", port_name));
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let stop = stop.clone();
+        let port_name = port_name.to_string();
+        let task = task.clone();
+        std::thread::spawn(move || {
+            task(port_name.clone(), stop);
+            restore_read_timeout(&port_name);
+        })
+    };
+
+    listeners.insert(port_name.to_string(), ListenerHandle {
+        stop,
+        thread,
+        task: Some(task),
+    });
+
+    Ok(())
+}
+
+// 通过 Channel 批量推送接收数据：凑够 batch_size 字节或静默 flush_idle_ms 后发送一批，
+// 显著减少高速采集时的 IPC 消息数量。与 start_serial_listener 互斥，用 stop_serial_listener 停止
+#[tauri::command]
+fn start_serial_channel(
+    port_name: String,
+    session_id: String,
+    channel: tauri::ipc::Channel<Vec<u8>>,
+    batch_size: Option<usize>,
+    flush_idle_ms: Option<u64>,
+) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+    let batch_size = batch_size.unwrap_or(4096).max(1);
+    let flush_idle = Duration::from_millis(flush_idle_ms.unwrap_or(20));
+
+    spawn_port_reader(&port_name, move |port_name, stop| {
+        run_channel_reader(channel.clone(), port_name, batch_size, flush_idle, stop);
+    })?;
+
+    Ok(format!("Channel reader for {} started", port_name))
+}
+
+// Channel 批量推送线程主循环
+fn run_channel_reader(
+    channel: tauri::ipc::Channel<Vec<u8>>,
+    port_name: String,
+    batch_size: usize,
+    flush_idle: Duration,
+    stop: Arc<AtomicBool>,
+) {
+    let mut batch: Vec<u8> = Vec::with_capacity(batch_size);
+    let mut last_data = std::time::Instant::now();
+
+    while !stop.load(Ordering::Relaxed) {
+        if is_port_paused(&port_name) {
+            std::thread::sleep(PAUSED_POLL_INTERVAL);
+            continue;
+        }
+        match read_port_bytes_poll(&port_name, LISTENER_READ_TIMEOUT_MS, usize::MAX) {
+            Ok(data) if !data.is_empty() => {
+                batch.extend_from_slice(&data);
+                last_data = std::time::Instant::now();
+            }
+            Ok(_) => {
+                // 虚拟串口读取不阻塞，避免空转
+                if port_name.starts_with("VIRTUAL-") {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+            }
+            Err(error) => {
+                log_message(LogLevel::Warning, format!("串口 {} Channel 读取出错: {}", port_name, error));
+                break;
+            }
+        }
+
+        let idle_flush = !batch.is_empty() && last_data.elapsed() >= flush_idle;
+        if batch.len() >= batch_size || idle_flush {
+            let full = std::mem::replace(&mut batch, Vec::with_capacity(batch_size));
+            if channel.send(full).is_err() {
+                // 前端已关闭 Channel
+                break;
+            }
+        }
+    }
+
+    if !batch.is_empty() {
+        let _ = channel.send(batch);
+    }
+}
+
+// 监听线程主循环
+fn run_listener(
+    window: tauri::Window,
+    port_name: String,
+    poll_interval_ms: u64,
+    read_chunk_size: usize,
+    stop: Arc<AtomicBool>,
+) {
+    let max_idle_sleep = Duration::from_millis(poll_interval_ms);
+    let mut idle_sleep = Duration::from_millis(1);
+
+    while !stop.load(Ordering::Relaxed) {
+        if is_port_paused(&port_name) {
+            std::thread::sleep(PAUSED_POLL_INTERVAL);
+            continue;
+        }
+        match read_port_bytes_poll(&port_name, LISTENER_READ_TIMEOUT_MS, read_chunk_size) {
+            Ok(data) if !data.is_empty() => {
+                // 有数据流动时快速轮询
+                idle_sleep = Duration::from_millis(1);
+                emit_frames(&window, &port_name, &data);
+                let _ = window.emit("serial-data", SerialDataEvent {
+                    port_name: port_name.clone(),
+                    data,
+                });
+            }
+            Ok(_) => {
+                // 空闲时逐步退避，避免空转占满 CPU
+                std::thread::sleep(idle_sleep);
+                idle_sleep = (idle_sleep * 2).min(max_idle_sleep);
+            }
+            Err(error) => {
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                log_message(LogLevel::Warning, format!("串口 {} 监听出错: {}", port_name, error));
+                let _ = window.emit("serial-error", SerialErrorEvent {
+                    port_name: port_name.clone(),
+                    error,
+                });
+                // 启用了自动重连时尝试重新打开，成功后继续监听
+                if !try_reconnect(&window, &port_name, &stop) {
+                    break;
+                }
+                idle_sleep = Duration::from_millis(1);
+            }
+        }
+    }
+}
+
+// 按最近一次的配置重新打开断开的串口，会话保持不变；返回是否重连成功
+fn try_reconnect(window: &tauri::Window, port_name: &str, stop: &AtomicBool) -> bool {
+    let policy = AUTO_RECONNECT.lock()
+        .expect("Failed to lock AUTO_RECONNECT mutex")
+        .get(port_name)
+        .copied();
+    let has_config = PORT_CONFIGS.lock()
+        .expect("Failed to lock PORT_CONFIGS mutex")
+        .contains_key(port_name);
+    let policy = match policy {
+        Some(policy) if has_config => policy,
+        _ => return false,
+    };
+
+    for attempt in 1..=policy.max_attempts {
+        let _ = window.emit("serial-reconnecting", SerialReconnectEvent {
+            port_name: port_name.to_string(),
+            attempt,
+            max_attempts: policy.max_attempts,
+        });
+
+        // 分段等待，便于关闭端口时及时退出
+        let deadline = std::time::Instant::now() + Duration::from_millis(policy.delay_ms);
+        while std::time::Instant::now() < deadline {
+            if stop.load(Ordering::Relaxed) {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        match reopen_port(port_name) {
+            Ok(_) => {
+                log_message(LogLevel::Debug, format!("串口 {} 第 {} 次重连成功", port_name, attempt));
+                let _ = window.emit("serial-reconnected", SerialReconnectEvent {
+                    port_name: port_name.to_string(),
+                    attempt,
+                    max_attempts: policy.max_attempts,
+                });
+                return true;
+            }
+            Err(e) => {
+                log_message(LogLevel::Warning, format!("串口 {} 第 {} 次重连失败: {}", port_name, attempt, e));
+            }
+        }
+    }
+
+    let _ = window.emit("serial-error", SerialErrorEvent {
+        port_name: port_name.to_string(),
+        error: format!("Gave up reconnecting after {} attempts", policy.max_attempts),
+    });
+    false
+}
+
+// 启用自动重连：后台监听检测到断开后按最近一次的配置重新打开
+#[tauri::command]
+fn enable_auto_reconnect(
+    port_name: String,
+    session_id: String,
+    max_attempts: u32,
+    delay_ms: u64,
+) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+
+    if max_attempts == 0 {
+        return Err("max_attempts must be at least 1".to_string());
+    }
+    // 虚拟串口可配合 set_virtual_fault 模拟断线来测试重连
+    let is_open = if port_name.starts_with("VIRTUAL-") {
+        VIRTUAL_BUFFERS.lock()
+            .expect("Failed to lock VIRTUAL_BUFFERS mutex")
+            .contains_key(&port_name)
+    } else {
+        SERIAL_PORTS.lock()
+            .expect("Failed to lock SERIAL_PORTS mutex")
+            .contains_key(&port_name)
+    };
+    if !is_open || !PORT_CONFIGS.lock()
+        .expect("Failed to lock PORT_CONFIGS mutex")
+        .contains_key(&port_name)
+    {
+        return Err(format!("Port {} not found", port_name));
+    }
+
+    AUTO_RECONNECT.lock()
+        .expect("Failed to lock AUTO_RECONNECT mutex")
+        .insert(port_name.clone(), ReconnectPolicy { max_attempts, delay_ms });
+
+    Ok(format!(
+        "Auto reconnect enabled for {} ({} attempts, {} ms apart)",
+        port_name, max_attempts, delay_ms
+    ))
+}
+
+// 发送后在 listen_ms 内通过 serial-data 事件推送收到的所有数据（包括设备主动上报）
+// 临时监听与普通监听一样登记在 LISTENERS 中，可被 stop_serial_listener / 关闭端口停止，到期后自行注销
+#[tauri::command]
+fn write_then_listen(
+    window: tauri::Window,
+    port_name: String,
+    session_id: String,
+    data: String,
+    is_hex: bool,
+    listen_ms: u64,
+) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+    let bytes_to_send = encode_payload(&data, is_hex, &WriteOptions::default())?;
+    if bytes_to_send.is_empty() {
+        return Err("nothing to send".to_string());
+    }
+
+    // 已有后台监听时数据会经由它推送，无需再开线程；否则先启动监听再发送，避免漏掉快速应答
+    let running = LISTENERS.lock()
+        .expect("Failed to lock LISTENERS mutex")
+        .contains_key(&port_name);
+    if !running {
+        let deadline = std::time::Instant::now() + Duration::from_millis(listen_ms);
+        spawn_port_reader(&port_name, move |port_name, stop| {
+            run_timed_listener(window.clone(), port_name, deadline, stop);
+        })?;
+    }
+
+    let sent = match write_port_bytes(&port_name, &bytes_to_send, &data) {
+        Ok(sent) => sent,
+        Err(e) => {
+            if !running {
+                stop_listener(&port_name);
+            }
+            return Err(e);
+        }
+    };
+
+    if running {
+        Ok(format!("Sent {} bytes, responses delivered by the running listener", sent))
+    } else {
+        Ok(format!("Sent {} bytes, listening for {} ms", sent, listen_ms))
+    }
+}
+
+// write_then_listen 的临时监听主循环：到达 deadline 或被停止时退出，暂停期间不读取
+fn run_timed_listener(
+    window: tauri::Window,
+    port_name: String,
+    deadline: std::time::Instant,
+    stop: Arc<AtomicBool>,
+) {
+    while !stop.load(Ordering::Relaxed) {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        if is_port_paused(&port_name) {
+            std::thread::sleep(PAUSED_POLL_INTERVAL.min(remaining));
+            continue;
+        }
+        let timeout_ms = (remaining.as_millis() as u64).clamp(1, LISTENER_READ_TIMEOUT_MS);
+        match read_port_bytes_poll(&port_name, timeout_ms, READ_CHUNK_SIZE) {
+            Ok(data) if !data.is_empty() => {
+                emit_frames(&window, &port_name, &data);
+                let _ = window.emit("serial-data", SerialDataEvent {
+                    port_name: port_name.clone(),
+                    data,
+                });
+            }
+            Ok(_) => {
+                // 虚拟串口读取不阻塞，稍作等待避免空转
+                if port_name.starts_with("VIRTUAL-") {
+                    std::thread::sleep(remaining.min(Duration::from_millis(1)));
+                }
+            }
+            Err(error) => {
+                if stop.load(Ordering::Relaxed) {
+                    return;
+                }
+                let _ = window.emit("serial-error", SerialErrorEvent {
+                    port_name: port_name.clone(),
+                    error,
+                });
+                break;
+            }
+        }
+    }
+
+    // 自行结束时注销；句柄已被 stop_listener 取走（或换成了新的监听）时不处理
+    if !stop.load(Ordering::Relaxed) {
+        let mut listeners = LISTENERS.lock()
+            .expect("Failed to lock LISTENERS mutex");
+        if listeners.get(&port_name).is_some_and(|handle| Arc::ptr_eq(&handle.stop, &stop)) {
+            listeners.remove(&port_name);
+        }
+    }
+}
+
+// 若端口注册了帧解析器，解析数据并推送 serial-frame / serial-frame-error 事件
+fn emit_frames(window: &tauri::Window, port_name: &str, data: &[u8]) {
+    let outputs = {
+        let mut parsers = FRAME_PARSERS.lock()
+            .expect("Failed to lock FRAME_PARSERS mutex");
+        match parsers.get_mut(port_name) {
+            Some(parser) => parser.feed(data),
+            None => return,
+        }
+    };
+
+    for output in outputs {
+        let _ = match output {
+            FrameOutput::Frame { payload, raw } => window.emit("serial-frame", SerialFrameEvent {
+                port_name: port_name.to_string(),
+                payload,
+                raw,
+            }),
+            FrameOutput::Error { reason, bytes } => window.emit("serial-frame-error", SerialFrameErrorEvent {
+                port_name: port_name.to_string(),
+                reason,
+                bytes,
+            }),
+        };
+    }
+}
+
+// 为端口注册帧解析器，监听线程收到的数据会按此格式拆帧
+#[tauri::command]
+fn register_frame_parser(
+    port_name: String,
+    session_id: String,
+    header_byte: u8,
+    length_field_offset: usize,
+    length_field_size: usize,
+    has_crc16: bool,
+) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+
+    let spec = FrameSpec {
+        header_byte,
+        length_field_offset,
+        length_field_size,
+        has_crc16,
+    };
+    spec.validate()?;
+
+    let mut parsers = FRAME_PARSERS.lock()
+        .expect("Failed to lock FRAME_PARSERS mutex");
+    parsers.insert(port_name.clone(), FrameParser::new(spec));
+
+    Ok(format!("Frame parser registered for {}", port_name))
+}
+
+// 移除端口的帧解析器
 #[tauri::command]
-fn write_serial_data(port_name: String, data: String, is_hex: bool) -> Result<String, String> {
-    let bytes_to_send: Vec<u8> = if is_hex {
-        hex_string_to_bytes(&data)
-            .map_err(|e| format!("Invalid hex string: {}", e))?
+fn unregister_frame_parser(port_name: String, session_id: String) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+
+    let mut parsers = FRAME_PARSERS.lock()
+        .expect("Failed to lock FRAME_PARSERS mutex");
+    if parsers.remove(&port_name).is_some() {
+        Ok(format!("Frame parser removed for {}", port_name))
+    } else {
+        Err(format!("No frame parser registered for {}", port_name))
+    }
+}
+
+// 停止并等待监听线程退出，未运行时返回 false
+fn stop_listener(port_name: &str) -> bool {
+    // 先移出句柄再 join，避免持锁等待
+    let handle = LISTENERS.lock()
+        .expect("Failed to lock LISTENERS mutex")
+        .remove(port_name);
+
+    match handle {
+        Some(handle) => {
+            handle.stop.store(true, Ordering::Relaxed);
+            let _ = handle.thread.join();
+            true
+        }
+        None => false,
+    }
+}
+
+// 监听使用的是短轮询超时，监听线程退出后把读句柄恢复为用户最近一次读取使用的超时；
+// 仅由 register_listener 启动的线程在退出时调用，每次轮询都会重新设置超时，不会影响后续监听
+fn restore_read_timeout(port_name: &str) {
+    if port_name.starts_with("VIRTUAL-") {
+        return;
+    }
+    let timeout_ms = READ_TIMEOUTS.lock()
+        .expect("Failed to lock READ_TIMEOUTS mutex")
+        .get(port_name)
+        .copied()
+        .unwrap_or(OPEN_READ_TIMEOUT_MS);
+    if let Ok(port) = port_reader(port_name) {
+        let _ = port.lock()
+            .expect("Failed to lock port mutex")
+            .set_timeout(Duration::from_millis(timeout_ms));
+    }
+}
+
+// 监听重启结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ListenerRestartReport {
+    port_name: String,
+    waited_ms: u64,
+}
+
+// 重启卡住的监听：通知旧线程停止并最多等待 timeout_ms（默认 1000），旧线程退出后以相同参数启动新线程。
+// 超时仍未退出时报错且不启动新线程，避免两个线程同时读取同一端口；旧句柄保留在 LISTENERS 中，
+// 驱动调用返回后线程会自行退出，可再次调用本命令或关闭端口
+#[tauri::command(async)]
+fn restart_listener(
+    port_name: String,
+    session_id: String,
+    timeout_ms: Option<u64>,
+) -> Result<ListenerRestartReport, String> {
+    check_session(&port_name, &session_id)?;
+
+    // 等待期间句柄留在 LISTENERS 中，其它调用无法在旧线程退出前启动新的读取线程
+    let (stop, task) = {
+        let listeners = LISTENERS.lock()
+            .expect("Failed to lock LISTENERS mutex");
+        let handle = listeners.get(&port_name)
+            .ok_or_else(|| format!("No listener running for {}", port_name))?;
+        (handle.stop.clone(), handle.task.clone())
+    };
+    let task = task.ok_or_else(|| format!("Listener for {} cannot be restarted", port_name))?;
+
+    stop.store(true, Ordering::Relaxed);
+    let started = std::time::Instant::now();
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(1000));
+    let handle = loop {
+        let mut listeners = LISTENERS.lock()
+            .expect("Failed to lock LISTENERS mutex");
+        match listeners.get(&port_name) {
+            Some(handle) if Arc::ptr_eq(&handle.stop, &stop) => {
+                if handle.thread.is_finished() {
+                    break listeners.remove(&port_name).expect("listener handle present");
+                }
+            }
+            // 等待期间被 stop_serial_listener / 关闭端口处理掉了
+            _ => return Err(format!("Listener for {} was stopped during restart", port_name)),
+        }
+        drop(listeners);
+
+        if started.elapsed() >= timeout {
+            log_message(LogLevel::Warning, format!("串口 {} 的监听线程未响应停止信号，无法重启", port_name));
+            return Err(format!(
+                "Listener for {} did not stop within {} ms, it is still blocked in a read",
+                port_name,
+                timeout.as_millis()
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    };
+    let _ = handle.thread.join();
+    let waited_ms = started.elapsed().as_millis() as u64;
+
+    register_listener(&port_name, task)?;
+    Ok(ListenerRestartReport { port_name, waited_ms })
+}
+
+// 暂停端口的所有读写而不关闭句柄（控制线状态和配置保持不变），监听线程暂停读取
+#[tauri::command]
+fn pause_port(port_name: String, session_id: String) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+
+    let open = if port_name.starts_with("VIRTUAL-") {
+        VIRTUAL_BUFFERS.lock()
+            .expect("Failed to lock VIRTUAL_BUFFERS mutex")
+            .contains_key(&port_name)
+    } else {
+        SERIAL_PORTS.lock()
+            .expect("Failed to lock SERIAL_PORTS mutex")
+            .contains_key(&port_name)
+    };
+    if !open {
+        return Err(format!("Port {} not found", port_name));
+    }
+
+    if !PAUSED_PORTS.lock()
+        .expect("Failed to lock PAUSED_PORTS mutex")
+        .insert(port_name.clone())
+    {
+        return Err(format!("Port {} is already paused", port_name));
+    }
+    Ok(format!("Port {} paused", port_name))
+}
+
+// 恢复被暂停端口的读写
+#[tauri::command]
+fn resume_port(port_name: String, session_id: String) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+
+    if PAUSED_PORTS.lock()
+        .expect("Failed to lock PAUSED_PORTS mutex")
+        .remove(&port_name)
+    {
+        Ok(format!("Port {} resumed", port_name))
+    } else {
+        Err(format!("Port {} is not paused", port_name))
+    }
+}
+
+// 自动关闭监视线程的检查间隔
+const AUTO_CLOSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// 停止端口的自动关闭监视（线程在下一次检查时退出，不等待）
+fn stop_auto_close(port_name: &str) -> bool {
+    match AUTO_CLOSE.lock()
+        .expect("Failed to lock AUTO_CLOSE mutex")
+        .remove(port_name)
+    {
+        Some(stop) => {
+            stop.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+// 设置端口自动关闭：idle_ms 内无读写，或自打开起超过 max_ms 时关闭端口并推送 serial-auto-closed；
+// 自动重连/写入恢复重新打开后两个计时都从头开始。两者都不设置（或为 0）时取消自动关闭。
+// 计时只作用于设置它的会话：端口被释放后停止，关闭前再次确认端口仍归该会话所有
+#[tauri::command]
+fn set_auto_close(
+    window: tauri::Window,
+    port_name: String,
+    session_id: String,
+    idle_ms: Option<u64>,
+    max_ms: Option<u64>,
+) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+    if !PORT_STATS.lock()
+        .expect("Failed to lock PORT_STATS mutex")
+        .contains_key(&port_name)
+    {
+        return Err(format!("Port {} not found", port_name));
+    }
+
+    stop_auto_close(&port_name);
+    let idle = idle_ms.filter(|&ms| ms > 0).map(Duration::from_millis);
+    let max = max_ms.filter(|&ms| ms > 0).map(Duration::from_millis);
+    if idle.is_none() && max.is_none() {
+        return Ok(format!("Auto-close disabled for {}", port_name));
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    AUTO_CLOSE.lock()
+        .expect("Failed to lock AUTO_CLOSE mutex")
+        .insert(port_name.clone(), stop.clone());
+
+    let name = port_name.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(AUTO_CLOSE_POLL_INTERVAL);
+        // 已停止，或端口已关闭后被其它会话重新打开
+        if stop.load(Ordering::Relaxed) || check_session(&name, &session_id).is_err() {
+            return;
+        }
+        // 统计记录不存在说明端口已被关闭；每次重新读取，重新打开时 reopen_port 会重置时间
+        let Some((opened_at, last_io)) = PORT_STATS.lock()
+            .expect("Failed to lock PORT_STATS mutex")
+            .get(&name)
+            .map(|entry| (entry.opened_at, entry.last_io))
+        else {
+            return;
+        };
+
+        let (reason, elapsed) = if max.is_some_and(|max| opened_at.elapsed() >= max) {
+            ("max_duration", opened_at.elapsed())
+        } else if idle.is_some_and(|idle| last_io.elapsed() >= idle) {
+            ("idle", last_io.elapsed())
+        } else {
+            continue;
+        };
+
+        // close_port 会清理 AUTO_CLOSE 中本线程的条目
+        close_port(&name);
+        log_message(LogLevel::Info, format!("串口 {} 已自动关闭: {}", name, reason));
+        let _ = window.emit("serial-auto-closed", SerialAutoClosedEvent {
+            port_name: name.clone(),
+            reason: reason.to_string(),
+            elapsed_ms: elapsed.as_millis() as u64,
+        });
+        return;
+    });
+
+    Ok(format!("Auto-close enabled for {}", port_name))
+}
+
+// 停止后台监听
+#[tauri::command]
+fn stop_serial_listener(port_name: String, session_id: String) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+
+    if stop_listener(&port_name) {
+        Ok(format!("Listener for {} stopped", port_name))
+    } else {
+        Err(format!("No listener running for {}", port_name))
+    }
+}
+
+// 精确读取 n 个字节，跨多次读取累积，超时报错并附带已收到的数据
+#[tauri::command(async)]
+fn read_exact_serial(
+    port_name: String,
+    session_id: String,
+    n: usize,
+    timeout_ms: Option<u64>,
+) -> Result<Vec<u8>, String> {
+    use std::time::Instant;
+
+    check_session(&port_name, &session_id)?;
+    let timeout_ms = resolve_read_timeout(timeout_ms);
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    let mut received: Vec<u8> = Vec::with_capacity(n);
+
+    while received.len() < n {
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "Timed out after receiving {} of {} bytes: {}",
+                received.len(),
+                n,
+                bytes_to_hex_string(&received)
+            ));
+        }
+        received.extend(read_port_chunk_max(&port_name, deadline, n - received.len())?);
+    }
+
+    Ok(received)
+}
+
+// 读取并丢弃数据，直到线路静默 idle_gap_ms，使下一次读取从帧边界开始
+// 返回丢弃的字节数；timeout_ms 内始终未出现静默时报错
+#[tauri::command(async)]
+fn sync_to_idle(
+    port_name: String,
+    session_id: String,
+    idle_gap_ms: u64,
+    timeout_ms: Option<u64>,
+) -> Result<usize, String> {
+    use std::time::Instant;
+
+    check_session(&port_name, &session_id)?;
+    let timeout_ms = resolve_read_timeout(timeout_ms);
+    let idle_gap = Duration::from_millis(idle_gap_ms.max(1));
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    let mut last_activity = Instant::now();
+    let mut discarded = 0usize;
+
+    loop {
+        let idle_deadline = last_activity + idle_gap;
+        if idle_deadline <= Instant::now() {
+            return Ok(discarded);
+        }
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "Line never went idle for {} ms within {} ms ({} bytes discarded)",
+                idle_gap_ms, timeout_ms, discarded
+            ));
+        }
+
+        let data = read_port_chunk(&port_name, idle_deadline.min(deadline))?;
+        if !data.is_empty() {
+            discarded += data.len();
+            last_activity = Instant::now();
+        }
+    }
+}
+
+// read_response 的结果：完整应答及解码信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ResponseRead {
+    bytes: Vec<u8>,
+    hex: String,
+    text: String,
+    duration_ms: u64,
+    // 实际执行的底层读取次数
+    read_ops: u32,
+}
+
+// 读取一段完整应答：收到首字节后线路静默 idle_gap_ms 即视为结束，
+// 达到 max_bytes 或 overall_timeout_ms 时也结束；整段时间没有数据时返回空结果
+#[tauri::command(async)]
+fn read_response(
+    port_name: String,
+    session_id: String,
+    idle_gap_ms: u64,
+    max_bytes: Option<usize>,
+    overall_timeout_ms: Option<u64>,
+    encoding: Option<String>,
+) -> Result<ResponseRead, String> {
+    use std::time::Instant;
+
+    check_session(&port_name, &session_id)?;
+    let encoding = encoding.unwrap_or_else(|| "utf-8".to_string());
+    // 先校验编码，避免读走数据后才报错
+    decode_bytes(&[], &encoding)?;
+
+    let started = Instant::now();
+    let idle_gap = Duration::from_millis(idle_gap_ms.max(1));
+    let deadline = started + Duration::from_millis(resolve_read_timeout(overall_timeout_ms));
+    let max_bytes = max_bytes.unwrap_or(usize::MAX);
+    let mut received: Vec<u8> = Vec::new();
+    let mut last_activity: Option<Instant> = None;
+    let mut read_ops = 0u32;
+
+    while received.len() < max_bytes {
+        let now = Instant::now();
+        if now >= deadline || last_activity.is_some_and(|last| last + idle_gap <= now) {
+            break;
+        }
+        let until = match last_activity {
+            Some(last) => (last + idle_gap).min(deadline),
+            None => deadline,
+        };
+        let data = read_port_chunk_max(&port_name, until, max_bytes - received.len())?;
+        read_ops += 1;
+        if !data.is_empty() {
+            received.extend(data);
+            last_activity = Some(Instant::now());
+        }
+    }
+
+    Ok(ResponseRead {
+        hex: bytes_to_hex_string(&received),
+        text: decode_bytes(&received, &encoding)?,
+        bytes: received,
+        duration_ms: started.elapsed().as_millis() as u64,
+        read_ops,
+    })
+}
+
+// 在指定时长内读取数据并统计每个字节值出现的次数（长度 256）
+#[tauri::command(async)]
+fn byte_histogram(
+    port_name: String,
+    session_id: String,
+    duration_ms: u64,
+) -> Result<Vec<u32>, String> {
+    use std::time::Instant;
+
+    check_session(&port_name, &session_id)?;
+    let deadline = Instant::now() + Duration::from_millis(duration_ms);
+    let mut counts = vec![0u32; 256];
+
+    while Instant::now() < deadline {
+        for byte in read_port_chunk(&port_name, deadline)? {
+            counts[byte as usize] = counts[byte as usize].saturating_add(1);
+        }
+    }
+
+    Ok(counts)
+}
+
+// 接收间隔统计结果（单位：微秒）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TimingReport {
+    bytes: usize,
+    chunks: usize,
+    min_gap: u64,
+    mean_gap: f64,
+    median_gap: u64,
+    max_gap: u64,
+    // 明显大于中位数的间隔中最小的一个，可作为分包的 idle_gap 参考；未检测到时为 None
+    frame_gap: Option<u64>,
+    // 按 frame_gap 切分得到的帧数
+    frames: usize,
+}
+
+// 超过中位数多少倍的间隔视为帧间隔
+const FRAME_GAP_FACTOR: u64 = 4;
+
+// 在指定时长内读取数据并统计相邻数据块之间的到达间隔
+// 同一次读取返回的字节无法区分到达时间，按一个数据块计算
+#[tauri::command(async)]
+fn timing_report(
+    port_name: String,
+    session_id: String,
+    duration_ms: u64,
+) -> Result<TimingReport, String> {
+    use std::time::Instant;
+
+    check_session(&port_name, &session_id)?;
+    let deadline = Instant::now() + Duration::from_millis(duration_ms);
+    let mut total_bytes = 0usize;
+    let mut last_arrival: Option<Instant> = None;
+    let mut gaps: Vec<u64> = Vec::new();
+
+    while Instant::now() < deadline {
+        let data = read_port_chunk(&port_name, deadline)?;
+        if data.is_empty() {
+            continue;
+        }
+        let now = Instant::now();
+        if let Some(last) = last_arrival {
+            gaps.push(now.duration_since(last).as_micros() as u64);
+        }
+        last_arrival = Some(now);
+        total_bytes += data.len();
+    }
+
+    if gaps.is_empty() {
+        return Err(format!(
+            "Not enough data to measure gaps: received {} bytes in {} ms",
+            total_bytes, duration_ms
+        ));
+    }
+
+    let mut sorted = gaps.clone();
+    sorted.sort_unstable();
+    let median_gap = sorted[sorted.len() / 2];
+    let threshold = median_gap.max(1) * FRAME_GAP_FACTOR;
+    let frame_gap = sorted.iter().copied().find(|&gap| gap > threshold);
+    let frames = match frame_gap {
+        Some(frame_gap) => gaps.iter().filter(|&&gap| gap >= frame_gap).count() + 1,
+        None => 1,
+    };
+
+    Ok(TimingReport {
+        bytes: total_bytes,
+        chunks: gaps.len() + 1,
+        min_gap: sorted[0],
+        mean_gap: gaps.iter().sum::<u64>() as f64 / gaps.len() as f64,
+        median_gap,
+        max_gap: sorted[sorted.len() - 1],
+        frame_gap,
+        frames,
+    })
+}
+
+// 9 位多机通信模拟：逐字节切换奇/偶校验，使校验位等于期望的第 9 位
+// 性能代价：每个字节都要重新配置校验位并等待发送完成（drain），
+// USB 转串口上通常每字节需要 1ms 以上，吞吐量远低于正常写入，仅适合短帧
+#[tauri::command(async)]
+fn write_9bit(
+    port_name: String,
+    session_id: String,
+    bytes: Vec<u8>,
+    ninth_bits: Vec<bool>,
+) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+    if port_name.starts_with("VIRTUAL-") || port_name.starts_with(TCP_PREFIX) {
+        return Err("9-bit writes require a real serial port".to_string());
+    }
+    if bytes.len() != ninth_bits.len() {
+        return Err(format!(
+            "Length mismatch: {} bytes but {} ninth bits",
+            bytes.len(),
+            ninth_bits.len()
+        ));
+    }
+    if bytes.is_empty() {
+        return Err("nothing to send".to_string());
+    }
+
+    let port = port_writer(&port_name)?;
+    let mut port = port.lock()
+        .expect("Failed to lock port mutex");
+    write_with_ninth_bits(&mut **port, &bytes, &ninth_bits)?;
+    drop(port);
+    record_tx(&port_name, &bytes);
+
+    Ok(format!("Sent {} bytes", bytes.len()))
+}
+
+// 逐字节切换奇/偶校验发送，使每个字节的校验位等于对应的第 9 位，结束后恢复原校验设置
+fn write_with_ninth_bits(
+    port: &mut dyn SerialPort,
+    bytes: &[u8],
+    ninth_bits: &[bool],
+) -> Result<(), String> {
+    let original_parity = port.parity()
+        .map_err(|e| format!("Failed to read parity: {}", e))?;
+
+    let result = (|| -> Result<(), String> {
+        for (byte, ninth) in bytes.iter().zip(ninth_bits) {
+            // 偶校验时校验位 = 1 的个数的奇偶性，奇校验时相反
+            let ones_odd = byte.count_ones() % 2 == 1;
+            let parity = if ones_odd == *ninth {
+                serialport::Parity::Even
+            } else {
+                serialport::Parity::Odd
+            };
+            port.set_parity(parity)
+                .map_err(|e| format!("Failed to set parity: {}", e))?;
+            port.write_all(&[*byte])
+                .map_err(|e| format!("Failed to write data: {}", e))?;
+            // 必须等待该字节发出后才能切换下一字节的校验位
+            port.flush()
+                .map_err(|e| format!("Failed to drain output: {}", e))?;
+        }
+        Ok(())
+    })();
+
+    // 无论成功与否都恢复原校验设置
+    let restore = port.set_parity(original_parity);
+    result?;
+    restore.map_err(|e| format!("Failed to restore parity: {}", e))
+}
+
+// 多机通信：地址字节用 mark 校验（校验位恒为 1）发送，数据字节用 space 校验（恒为 0），
+// address_parity 为 "space" 时相反。结束后恢复原校验设置
+// 延迟代价：每次切换校验前都必须等待已写入的字节完全发出（drain），
+// USB 转串口上一次 drain 加重配置通常需要 1~16ms，地址与数据之间会出现明显间隙；
+// Linux 下用 CMSPAR 只切换两次，其它平台没有 mark/space 校验，退化为逐字节切换奇偶校验，
+// 数据越长越慢
+#[tauri::command(async)]
+fn write_multidrop(
+    port_name: String,
+    session_id: String,
+    address_byte: u8,
+    data_bytes: Vec<u8>,
+    address_parity: Option<String>,
+) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+    if port_name.starts_with("VIRTUAL-") || port_name.starts_with(TCP_PREFIX) {
+        return Err("Multidrop writes require a real serial port".to_string());
+    }
+    let address_mark = match address_parity.as_deref().unwrap_or("mark") {
+        "mark" => true,
+        "space" => false,
+        other => return Err(format!("Invalid address parity: {} (expected mark or space)", other)),
+    };
+
+    let port = port_writer(&port_name)?;
+    let mut port = port.lock()
+        .expect("Failed to lock port mutex");
+
+    #[cfg(target_os = "linux")]
+    write_mark_space(&mut **port, port_fd(&port_name)?, address_byte, &data_bytes, address_mark)?;
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let mut bytes = vec![address_byte];
+        bytes.extend_from_slice(&data_bytes);
+        let mut ninth_bits = vec![address_mark];
+        ninth_bits.resize(bytes.len(), !address_mark);
+        write_with_ninth_bits(&mut **port, &bytes, &ninth_bits)?;
+    }
+
+    drop(port);
+    let mut sent = vec![address_byte];
+    sent.extend_from_slice(&data_bytes);
+    record_tx(&port_name, &sent);
+
+    Ok(format!("Sent address {:02X} and {} data bytes", address_byte, data_bytes.len()))
+}
+
+// 通过 CMSPAR 设置 mark（PARODD）/ space 校验；TCSADRAIN 会等待已写入的数据发完再生效
+#[cfg(target_os = "linux")]
+fn write_mark_space(
+    port: &mut dyn SerialPort,
+    fd: std::os::unix::io::RawFd,
+    address_byte: u8,
+    data_bytes: &[u8],
+    address_mark: bool,
+) -> Result<(), String> {
+    let original = read_termios(fd)?;
+    let apply = |termios: &libc::termios| -> Result<(), String> {
+        if unsafe { libc::tcsetattr(fd, libc::TCSADRAIN, termios) } != 0 {
+            return Err(format!("tcsetattr failed: {}", std::io::Error::last_os_error()));
+        }
+        Ok(())
+    };
+    let with_parity = |mark: bool| {
+        let mut termios = original;
+        termios.c_cflag |= libc::PARENB | libc::CMSPAR;
+        if mark {
+            termios.c_cflag |= libc::PARODD;
+        } else {
+            termios.c_cflag &= !libc::PARODD;
+        }
+        termios
+    };
+
+    let result = (|| -> Result<(), String> {
+        apply(&with_parity(address_mark))?;
+        port.write_all(&[address_byte])
+            .map_err(|e| format!("Failed to write address byte: {}", e))?;
+        if !data_bytes.is_empty() {
+            apply(&with_parity(!address_mark))?;
+            port.write_all(data_bytes)
+                .map_err(|e| format!("Failed to write data: {}", e))?;
+        }
+        port.flush()
+            .map_err(|e| format!("Failed to drain output: {}", e))
+    })();
+
+    let restore = apply(&original);
+    result?;
+    restore.map_err(|e| format!("Failed to restore parity: {}", e))
+}
+
+// RS-485 半双工事务：拉起 RTS（驱动使能）→ 写入 → 等待发送完成 → 等待 turnaround_us →
+// 释放 RTS → 读取应答直到出现 terminator 或超时。整个过程在后端完成，避免 JS 调用之间的时序间隙
+// terminator 按 is_hex 解析；未指定时读满 read_timeout_ms 后返回收到的全部数据
+// rts_active_high 默认为 true（RTS 高电平使能发送器）
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+fn rs485_transaction(
+    port_name: String,
+    session_id: String,
+    data: String,
+    is_hex: bool,
+    turnaround_us: u64,
+    terminator: Option<String>,
+    read_timeout_ms: Option<u64>,
+    rts_active_high: Option<bool>,
+) -> Result<Vec<u8>, String> {
+    check_session(&port_name, &session_id)?;
+    let read_timeout_ms = resolve_read_timeout(read_timeout_ms);
+    if port_name.starts_with("VIRTUAL-") || port_name.starts_with(TCP_PREFIX) {
+        return Err("RS-485 transactions require a real serial port".to_string());
+    }
+    let bytes_to_send = encode_payload(&data, is_hex, &WriteOptions::default())?;
+    if bytes_to_send.is_empty() {
+        return Err("nothing to send".to_string());
+    }
+    let terminator = match terminator.as_deref() {
+        Some(t) if !t.is_empty() => Some(encode_payload(t, is_hex, &WriteOptions::default())?),
+        _ => None,
+    };
+    let active = rts_active_high.unwrap_or(true);
+
+    // 发送阶段全程持有写句柄，防止其它写入插入
+    {
+        let port = port_writer(&port_name)?;
+        let mut port = port.lock()
+            .expect("Failed to lock port mutex");
+        // 丢弃发送前残留的输入，避免与应答混在一起
+        let _ = port.clear(serialport::ClearBuffer::Input);
+
+        port.write_request_to_send(active)
+            .map_err(|e| format!("Failed to assert RTS: {}", e))?;
+        let sent = port.write_all(&bytes_to_send)
+            .and_then(|_| port.flush());
+        // USB 转换器的 drain 可能早于最后一个停止位结束，再等待 turnaround
+        spin_wait(Duration::from_micros(turnaround_us));
+        let released = port.write_request_to_send(!active);
+        sent.map_err(|e| format!("Failed to write data: {}", e))?;
+        released.map_err(|e| format!("Failed to release RTS: {}", e))?;
+    }
+    record_tx(&port_name, &bytes_to_send);
+
+    let deadline = std::time::Instant::now() + Duration::from_millis(read_timeout_ms);
+    let mut response = Vec::new();
+    while std::time::Instant::now() < deadline {
+        response.extend(read_port_chunk(&port_name, deadline)?);
+        if let Some(terminator) = &terminator {
+            if let Some(pos) = find_subsequence(&response, terminator) {
+                response.truncate(pos + terminator.len());
+                return Ok(response);
+            }
+        }
+    }
+
+    match terminator {
+        Some(_) => Err(format!(
+            "Timed out waiting for terminator after {} ms ({} bytes received: {})",
+            read_timeout_ms,
+            response.len(),
+            bytes_to_hex_string(&response)
+        )),
+        None => Ok(response),
+    }
+}
+
+// 查找子序列首次出现的位置
+fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(0);
+    }
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+// DMX512 时序参数
+const DMX_BAUD_RATE: u32 = 250000;
+const DMX_MAX_CHANNELS: usize = 512;
+const DMX_BREAK: Duration = Duration::from_micros(100);
+const DMX_MARK_AFTER_BREAK: Duration = Duration::from_micros(12);
+
+// 忙等待指定时长；thread::sleep 的精度通常只有毫秒级，无法满足 break 时序
+fn spin_wait(duration: Duration) {
+    let start = std::time::Instant::now();
+    while start.elapsed() < duration {
+        std::hint::spin_loop();
+    }
+}
+
+// 发送一帧 DMX512：break（约 100µs）+ mark-after-break + 起始码 0x00 + 通道数据
+// 端口需预先以 250000 波特率、8N2 打开
+#[tauri::command(async)]
+fn send_dmx_frame(
+    port_name: String,
+    session_id: String,
+    channels: Vec<u8>,
+) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+    if port_name.starts_with("VIRTUAL-") || port_name.starts_with(TCP_PREFIX) {
+        return Err("DMX512 requires a real serial port".to_string());
+    }
+    if channels.is_empty() || channels.len() > DMX_MAX_CHANNELS {
+        return Err(format!(
+            "DMX512 frame must have 1 to {} channels, got {}",
+            DMX_MAX_CHANNELS,
+            channels.len()
+        ));
+    }
+
+    let port = port_writer(&port_name)?;
+    let mut port = port.lock()
+        .expect("Failed to lock port mutex");
+
+    let baud_rate = port.baud_rate()
+        .map_err(|e| format!("Failed to read baud rate: {}", e))?;
+    if baud_rate != DMX_BAUD_RATE {
+        return Err(format!(
+            "DMX512 requires {} baud, port is configured for {}",
+            DMX_BAUD_RATE, baud_rate
+        ));
+    }
+
+    // 上一帧必须完全发出后才能拉 break，否则会截断最后的字节
+    port.flush()
+        .map_err(|e| format!("Failed to drain output: {}", e))?;
+
+    let break_started = std::time::Instant::now();
+    port.set_break()
+        .map_err(|e| format!("Failed to set break: {}", e))?;
+    spin_wait(DMX_BREAK);
+    let clear_result = port.clear_break();
+    let break_us = break_started.elapsed().as_micros();
+    clear_result.map_err(|e| format!("Failed to clear break: {}", e))?;
+    spin_wait(DMX_MARK_AFTER_BREAK);
+
+    let mut frame = Vec::with_capacity(channels.len() + 1);
+    frame.push(0x00);
+    frame.extend_from_slice(&channels);
+    port.write_all(&frame)
+        .map_err(|e| format!("Failed to write data: {}", e))?;
+    port.flush()
+        .map_err(|e| format!("Failed to drain output: {}", e))?;
+    drop(port);
+    record_tx(&port_name, &frame);
+
+    Ok(format!(
+        "Sent DMX512 frame with {} channels (break {} us)",
+        channels.len(),
+        break_us
+    ))
+}
+
+// 软件模拟 UART：逐位翻转控制线（dtr / rts，默认 dtr），true 为拉起控制线，每位忙等待 bit_duration_us
+// 精度很差，只适合低速且容错的场合：控制线经 ioctl / USB 控制传输设置，USB 转换器每次切换通常需要
+// 约 0.1~1ms 且抖动明显，线程被调度走时还会拉长个别位；实际可用速率一般在几百波特以内。
+// 按绝对时间点切换，误差不会逐位累积；结果中返回总耗时和最大滞后
+#[tauri::command(async)]
+fn bitbang_write(
+    port_name: String,
+    session_id: String,
+    bits: Vec<bool>,
+    bit_duration_us: u64,
+    line: Option<String>,
+) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+    if port_name.starts_with("VIRTUAL-") || port_name.starts_with(TCP_PREFIX) {
+        return Err("Bit-banging requires a real serial port".to_string());
+    }
+    let line = line.unwrap_or_else(|| "dtr".to_string()).to_ascii_lowercase();
+    if line != "dtr" && line != "rts" {
+        return Err(format!("Unsupported control line: {} (expected dtr or rts)", line));
+    }
+    if bit_duration_us == 0 {
+        return Err("bit_duration_us must be greater than 0".to_string());
+    }
+    if bits.is_empty() {
+        return Err("nothing to send".to_string());
+    }
+
+    let port = port_writer(&port_name)?;
+    let mut port = port.lock()
+        .expect("Failed to lock port mutex");
+
+    let bit_duration = Duration::from_micros(bit_duration_us);
+    let started = std::time::Instant::now();
+    let mut max_lag = Duration::ZERO;
+    for (i, &bit) in bits.iter().enumerate() {
+        let scheduled = started + bit_duration * i as u32;
+        max_lag = max_lag.max(std::time::Instant::now().saturating_duration_since(scheduled));
+        let result = if line == "dtr" {
+            port.write_data_terminal_ready(bit)
+        } else {
+            port.write_request_to_send(bit)
+        };
+        result.map_err(|e| format!("Failed to set {} at bit {}: {}", line.to_uppercase(), i, e))?;
+        spin_wait((scheduled + bit_duration).saturating_duration_since(std::time::Instant::now()));
+    }
+    drop(port);
+    record_bitbang(&port_name, bits.len());
+
+    Ok(format!(
+        "Bit-banged {} bits on {} in {} us (max lag {} us)",
+        bits.len(),
+        line.to_uppercase(),
+        started.elapsed().as_micros(),
+        max_lag.as_micros()
+    ))
+}
+
+// 分块发送的进度事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ChunkedWriteProgressEvent {
+    port_name: String,
+    bytes_sent: usize,
+    total_bytes: usize,
+    // 最近一个滑动窗口内的吞吐量
+    bytes_per_second: f64,
+}
+
+// 分块发送结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ChunkedWriteResult {
+    bytes_sent: usize,
+    elapsed_ms: u64,
+    average_bytes_per_second: f64,
+    peak_bytes_per_second: f64,
+}
+
+// 吞吐量滑动窗口长度
+const THROUGHPUT_WINDOW: Duration = Duration::from_millis(1000);
+
+// 分块发送大块数据，每块发送后通过 serial-write-progress 推送进度和滑动窗口吞吐量，
+// 便于区分是流控限速还是设备本身处理慢
+#[tauri::command(async)]
+fn write_serial_chunked(
+    window: tauri::Window,
+    port_name: String,
+    session_id: String,
+    data: String,
+    is_hex: bool,
+    chunk_size: Option<usize>,
+    options: Option<WriteOptions>,
+) -> Result<ChunkedWriteResult, String> {
+    use std::time::Instant;
+
+    check_session(&port_name, &session_id)?;
+    let options = options.unwrap_or_default();
+    let chunk_size = chunk_size.unwrap_or(1024).max(1);
+    let bytes_to_send = encode_payload(&data, is_hex, &options)?;
+    if bytes_to_send.is_empty() {
+        return Err("nothing to send".to_string());
+    }
+    let write_timeout = options.write_timeout_ms.map(Duration::from_millis);
+
+    let started = Instant::now();
+    // (时间点, 累计字节数)，只保留窗口内的采样
+    let mut samples: std::collections::VecDeque<(Instant, usize)> =
+        std::collections::VecDeque::from([(started, 0)]);
+    let mut bytes_sent = 0usize;
+    let mut peak = 0f64;
+
+    for chunk in bytes_to_send.chunks(chunk_size) {
+        bytes_sent += write_port_bytes_timeout(&port_name, chunk, "", write_timeout)
+            .map_err(|e| format!("{} (after {} bytes sent)", e, bytes_sent))?;
+
+        let now = Instant::now();
+        samples.push_back((now, bytes_sent));
+        while samples.len() > 2 && now.duration_since(samples[1].0) >= THROUGHPUT_WINDOW {
+            samples.pop_front();
+        }
+        let (window_start, window_bytes) = samples[0];
+        let span = now.duration_since(window_start).as_secs_f64();
+        let bytes_per_second = if span > 0.0 {
+            (bytes_sent - window_bytes) as f64 / span
+        } else {
+            0.0
+        };
+        // 窗口不足一半时波动太大，不计入峰值
+        if now.duration_since(started) >= THROUGHPUT_WINDOW / 2 {
+            peak = peak.max(bytes_per_second);
+        }
+
+        let _ = window.emit("serial-write-progress", ChunkedWriteProgressEvent {
+            port_name: port_name.clone(),
+            bytes_sent,
+            total_bytes: bytes_to_send.len(),
+            bytes_per_second,
+        });
+    }
+
+    let elapsed = started.elapsed();
+    let average = if elapsed.is_zero() {
+        0.0
+    } else {
+        bytes_sent as f64 / elapsed.as_secs_f64()
+    };
+    Ok(ChunkedWriteResult {
+        bytes_sent,
+        elapsed_ms: elapsed.as_millis() as u64,
+        average_bytes_per_second: average,
+        // 传输过短时以平均值作为峰值
+        peak_bytes_per_second: peak.max(average),
+    })
+}
+
+// HEX 流式上传进度事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HexUploadProgressEvent {
+    port_name: String,
+    bytes_sent: usize,
+    // 已处理的文件字节数与文件总大小，用于计算百分比
+    file_processed: u64,
+    file_size: u64,
+}
+
+// 增量 HEX 解析器：忽略空白，跨读取块保留未配对的半字节
+struct HexStreamParser {
+    pending_nibble: Option<u8>,
+    offset: u64,
+}
+
+impl HexStreamParser {
+    fn new() -> Self {
+        HexStreamParser { pending_nibble: None, offset: 0 }
+    }
+
+    // 解析一段文本，把得到的字节追加到 out
+    fn feed(&mut self, text: &[u8], out: &mut Vec<u8>) -> Result<(), String> {
+        for &c in text {
+            self.offset += 1;
+            if c.is_ascii_whitespace() {
+                continue;
+            }
+            let nibble = (c as char)
+                .to_digit(16)
+                .ok_or_else(|| format!("Invalid hex character '{}' at offset {}", c as char, self.offset - 1))?
+                as u8;
+            match self.pending_nibble.take() {
+                Some(high) => out.push((high << 4) | nibble),
+                None => self.pending_nibble = Some(nibble),
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&self) -> Result<(), String> {
+        if self.pending_nibble.is_some() {
+            return Err("Hex string length must be even".to_string());
+        }
+        Ok(())
+    }
+}
+
+// 逐块读取 HEX 文件，每解析出一块数据就交给 on_chunk
+fn stream_hex_file(
+    file_path: &str,
+    chunk_size: usize,
+    mut on_chunk: impl FnMut(&[u8], u64) -> Result<(), String>,
+) -> Result<(), String> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(file_path)
+        .map_err(|e| format!("Failed to open {}: {}", file_path, e))?;
+    let mut parser = HexStreamParser::new();
+    let mut text = vec![0u8; 64 * 1024];
+    let mut chunk = Vec::with_capacity(chunk_size);
+    let mut processed = 0u64;
+
+    loop {
+        let n = file.read(&mut text)
+            .map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+        if n == 0 {
+            break;
+        }
+        processed += n as u64;
+        for piece in text[..n].chunks(chunk_size) {
+            parser.feed(piece, &mut chunk)?;
+            if chunk.len() >= chunk_size {
+                on_chunk(&chunk, processed)?;
+                chunk.clear();
+            }
+        }
+    }
+    parser.finish()?;
+    if !chunk.is_empty() {
+        on_chunk(&chunk, processed)?;
+    }
+    Ok(())
+}
+
+// 流式发送大体积 HEX 文件：增量解析并分块写入，内存占用与文件大小无关
+// 先完整校验一遍格式，避免发送到一半才发现错误；进度通过 serial-upload-progress 事件推送
+#[tauri::command(async)]
+fn write_serial_hex_stream(
+    window: tauri::Window,
+    port_name: String,
+    session_id: String,
+    file_path: String,
+    chunk_size: Option<usize>,
+) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+    let chunk_size = chunk_size.unwrap_or(4096).max(1);
+    let file_size = std::fs::metadata(&file_path)
+        .map_err(|e| format!("Failed to read {}: {}", file_path, e))?
+        .len();
+
+    stream_hex_file(&file_path, chunk_size, |_, _| Ok(()))?;
+
+    let mut bytes_sent = 0usize;
+    stream_hex_file(&file_path, chunk_size, |chunk, processed| {
+        bytes_sent += write_port_bytes(&port_name, chunk, "")
+            .map_err(|e| format!("{} (after {} bytes sent)", e, bytes_sent))?;
+        let _ = window.emit("serial-upload-progress", HexUploadProgressEvent {
+            port_name: port_name.clone(),
+            bytes_sent,
+            file_processed: processed,
+            file_size,
+        });
+        Ok(())
+    })?;
+
+    if bytes_sent == 0 {
+        return Err("nothing to send".to_string());
+    }
+    Ok(format!("Sent {} bytes from {}", bytes_sent, file_path))
+}
+
+// YMODEM 传输进度事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct YmodemProgressEvent {
+    port_name: String,
+    // send / receive
+    direction: String,
+    file_index: usize,
+    file_name: String,
+    bytes_transferred: u64,
+    // 接收时头部未给出大小则为 0
+    file_size: u64,
+}
+
+// 将打开的串口适配为 YMODEM 收发通道
+struct PortLink {
+    port_name: String,
+    pending: std::collections::VecDeque<u8>,
+}
+
+impl ymodem::Link for PortLink {
+    fn send(&mut self, data: &[u8]) -> Result<(), String> {
+        write_port_bytes(&self.port_name, data, "").map(|_| ())
+    }
+
+    fn recv_byte(&mut self, timeout: Duration) -> Result<Option<u8>, String> {
+        let deadline = std::time::Instant::now() + timeout;
+        while self.pending.is_empty() && std::time::Instant::now() < deadline {
+            self.pending.extend(read_port_chunk(&self.port_name, deadline)?);
+        }
+        Ok(self.pending.pop_front())
+    }
+}
+
+// YMODEM 需要独占接收数据，后台监听会抢走应答字节
+fn ymodem_link(port_name: &str) -> Result<PortLink, String> {
+    if LISTENERS.lock()
+        .expect("Failed to lock LISTENERS mutex")
+        .contains_key(port_name)
+    {
+        return Err(format!("Stop the listener on {} before a YMODEM transfer", port_name));
+    }
+    Ok(PortLink {
+        port_name: port_name.to_string(),
+        pending: std::collections::VecDeque::new(),
+    })
+}
+
+fn emit_ymodem_progress(
+    window: &tauri::Window,
+    port_name: &str,
+    direction: &str,
+) -> impl FnMut(usize, &str, u64, u64) {
+    let window = window.clone();
+    let port_name = port_name.to_string();
+    let direction = direction.to_string();
+    move |file_index, file_name, bytes_transferred, file_size| {
+        let _ = window.emit("serial-ymodem-progress", YmodemProgressEvent {
+            port_name: port_name.clone(),
+            direction: direction.clone(),
+            file_index,
+            file_name: file_name.to_string(),
+            bytes_transferred,
+            file_size,
+        });
+    }
+}
+
+// 通过 YMODEM 批量发送文件（块 0 携带文件名和大小，1K 数据块 + CRC16），
+// 进度通过 serial-ymodem-progress 事件推送
+#[tauri::command(async)]
+fn ymodem_send(
+    window: tauri::Window,
+    port_name: String,
+    session_id: String,
+    file_paths: Vec<String>,
+) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+    if file_paths.is_empty() {
+        return Err("No files to send".to_string());
+    }
+    let mut files = Vec::new();
+    for path in &file_paths {
+        let name = std::path::Path::new(path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| format!("Invalid file path: {}", path))?
+            .to_string();
+        let data = std::fs::read(path)
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        files.push((name, data));
+    }
+
+    let mut link = ymodem_link(&port_name)?;
+    let mut on_progress = emit_ymodem_progress(&window, &port_name, "send");
+    ymodem::send_files(&mut link, &files, &mut on_progress)?;
+
+    let total: usize = files.iter().map(|(_, data)| data.len()).sum();
+    log_message(LogLevel::Info, format!("YMODEM 已发送 {} 个文件到 {}", files.len(), port_name));
+    Ok(format!("Sent {} file(s), {} bytes via YMODEM", files.len(), total))
+}
+
+// 通过 YMODEM 接收一批文件，保存到 dest_dir，返回保存的文件路径
+#[tauri::command(async)]
+fn ymodem_receive(
+    window: tauri::Window,
+    port_name: String,
+    session_id: String,
+    dest_dir: String,
+) -> Result<Vec<String>, String> {
+    check_session(&port_name, &session_id)?;
+    let dest = std::path::Path::new(&dest_dir);
+    if !dest.is_dir() {
+        return Err(format!("Destination {} is not a directory", dest_dir));
+    }
+
+    let mut link = ymodem_link(&port_name)?;
+    let mut on_progress = emit_ymodem_progress(&window, &port_name, "receive");
+    let mut saved = Vec::new();
+    let mut on_file = |name: &str, data: &[u8]| -> Result<(), String> {
+        // 只取文件名部分，防止对端通过路径写到目标目录之外
+        let file_name = std::path::Path::new(name)
+            .file_name()
+            .ok_or_else(|| format!("Invalid file name from sender: {}", name))?;
+        let path = dest.join(file_name);
+        std::fs::write(&path, data)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        saved.push(path.display().to_string());
+        Ok(())
+    };
+    ymodem::receive_files(&mut link, &mut on_file, &mut on_progress)?;
+
+    log_message(LogLevel::Info, format!("YMODEM 从 {} 接收了 {} 个文件", port_name, saved.len()));
+    Ok(saved)
+}
+
+// 回放文件中单行的发送结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ReplayLineReport {
+    line_number: usize,
+    bytes_sent: usize,
+    error: Option<String>,
+}
+
+// 回放 HEX 帧文件：每行一帧，跳过空行和 # 注释，按间隔依次发送
+#[tauri::command(async)]
+fn replay_hex_file(
+    port_name: String,
+    session_id: String,
+    file_path: String,
+    inter_frame_delay_ms: u64,
+) -> Result<Vec<ReplayLineReport>, String> {
+    check_session(&port_name, &session_id)?;
+    let content = std::fs::read_to_string(&file_path)
+        .map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+
+    let mut reports = Vec::new();
+    let mut first_frame = true;
+
+    for (index, line) in content.lines().enumerate() {
+        // 去掉行内注释
+        let frame = line.split('#').next().unwrap_or("").trim();
+        if frame.is_empty() {
+            continue;
+        }
+
+        let line_number = index + 1;
+        let bytes_to_send = match hex_string_to_bytes(frame) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                reports.push(ReplayLineReport {
+                    line_number,
+                    bytes_sent: 0,
+                    error: Some(format!("Invalid hex string: {}", e)),
+                });
+                continue;
+            }
+        };
+
+        if !first_frame && inter_frame_delay_ms > 0 {
+            std::thread::sleep(Duration::from_millis(inter_frame_delay_ms));
+        }
+        first_frame = false;
+
+        match write_port_bytes(&port_name, &bytes_to_send, frame) {
+            Ok(sent) => reports.push(ReplayLineReport {
+                line_number,
+                bytes_sent: sent,
+                error: None,
+            }),
+            Err(e) => {
+                // 写入失败通常意味着端口已不可用，停止回放
+                reports.push(ReplayLineReport {
+                    line_number,
+                    bytes_sent: 0,
+                    error: Some(e),
+                });
+                break;
+            }
+        }
+    }
+
+    Ok(reports)
+}
+
+// 连接健康检查结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ConnectionHealth {
+    healthy: bool,
+    detail: String,
+}
+
+// 检查连接是否存活：有探测数据时发送并等待响应（可指定期望内容），
+// 否则仅通过查询接收缓冲确认句柄仍然有效
+#[tauri::command(async)]
+fn check_connection(
+    port_name: String,
+    session_id: String,
+    probe: Option<String>,
+    expected: Option<String>,
+    is_hex: Option<bool>,
+    timeout_ms: Option<u64>,
+) -> Result<ConnectionHealth, String> {
+    check_session(&port_name, &session_id)?;
+    let timeout_ms = resolve_read_timeout(timeout_ms);
+    let result = match probe.filter(|p| !p.is_empty()) {
+        Some(probe) => probe_connection(&port_name, &probe, expected.as_deref(), is_hex.unwrap_or(false), timeout_ms),
+        None => check_handle(&port_name),
+    };
+
+    Ok(match result {
+        Ok(detail) => ConnectionHealth { healthy: true, detail },
+        Err(detail) => ConnectionHealth { healthy: false, detail },
+    })
+}
+
+// 不发送数据，仅确认句柄可用
+fn check_handle(port_name: &str) -> Result<String, String> {
+    if port_name.starts_with("VIRTUAL-") {
+        let buffers = VIRTUAL_BUFFERS.lock()
+            .expect("Failed to lock VIRTUAL_BUFFERS mutex");
+        return if buffers.contains_key(port_name) {
+            Ok("Virtual port is open".to_string())
+        } else {
+            Err(format!("Virtual port {} not found", port_name))
+        };
+    }
+
+    let port = port_writer(port_name)?;
+    let port = port.lock()
+        .expect("Failed to lock port mutex");
+    port.bytes_to_read()
+        .map(|n| format!("Handle is valid, {} bytes pending", n))
+        .map_err(|e| format!("Handle check failed: {}", e))
+}
+
+// 发送探测数据并等待响应
+fn probe_connection(
+    port_name: &str,
+    probe: &str,
+    expected: Option<&str>,
+    is_hex: bool,
+    timeout_ms: u64,
+) -> Result<String, String> {
+    use std::time::Instant;
+
+    let (probe_bytes, expected_bytes) = if is_hex {
+        let probe_bytes = hex_string_to_bytes(probe)
+            .map_err(|e| format!("Invalid hex string: {}", e))?;
+        let expected_bytes = expected
+            .map(hex_string_to_bytes)
+            .transpose()
+            .map_err(|e| format!("Invalid hex string: {}", e))?;
+        (probe_bytes, expected_bytes)
     } else {
-        data.as_bytes().to_vec()
+        (probe.as_bytes().to_vec(), expected.map(|e| e.as_bytes().to_vec()))
     };
-    
-    // 虚拟串口逻辑
+
+    discard_pending_input(port_name)?;
+    write_port_bytes(port_name, &probe_bytes, probe)?;
+
+    let started = Instant::now();
+    let deadline = started + Duration::from_millis(timeout_ms);
+    let mut received: Vec<u8> = Vec::new();
+
+    while Instant::now() < deadline {
+        received.extend(read_port_chunk(port_name, deadline)?);
+        let matched = match &expected_bytes {
+            Some(expected) if !expected.is_empty() => {
+                received.windows(expected.len()).any(|w| w == expected.as_slice())
+            }
+            _ => !received.is_empty(),
+        };
+        if matched {
+            return Ok(format!("Response received in {} ms", started.elapsed().as_millis()));
+        }
+    }
+
+    if received.is_empty() {
+        Err(format!("No response within {} ms", timeout_ms))
+    } else {
+        Err(format!("Unexpected response ({} bytes)", received.len()))
+    }
+}
+
+// 生成伪随机字节（xorshift64，以时间为种子，仅用于测试数据）
+fn pseudo_random_bytes(length: usize) -> Vec<u8> {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let mut state = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+        | 1;
+    (0..length)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 24) as u8
+        })
+        .collect()
+}
+
+// 按名称生成测试图样
+fn generate_pattern(pattern: &str, length: usize) -> Result<Vec<u8>, String> {
+    match pattern {
+        "incrementing" => Ok((0..length).map(|i| i as u8).collect()),
+        "zeros" => Ok(vec![0x00; length]),
+        "ones" => Ok(vec![0xFF; length]),
+        "alternating" => Ok((0..length).map(|i| if i % 2 == 0 { 0x55 } else { 0xAA }).collect()),
+        "random" => Ok(pseudo_random_bytes(length)),
+        other => Err(format!("Unsupported pattern: {}", other)),
+    }
+}
+
+// 将数值按类型编码为字节（decode_one_number 的逆过程），超出范围时截断
+fn encode_one_number(value: f64, kind: &str, little_endian: bool) -> Result<Vec<u8>, String> {
+    macro_rules! encode {
+        ($t:ty) => {{
+            let v = value as $t;
+            if little_endian {
+                v.to_le_bytes().to_vec()
+            } else {
+                v.to_be_bytes().to_vec()
+            }
+        }};
+    }
+    Ok(match kind {
+        "u8" => vec![value as u8],
+        "u16" => encode!(u16),
+        "i16" => encode!(i16),
+        "u32" => encode!(u32),
+        "i32" => encode!(i32),
+        "f32" => encode!(f32),
+        "f64" => encode!(f64),
+        other => return Err(format!("Unsupported number kind: {}", other)),
+    })
+}
+
+// 各采样类型的取值范围，浮点为 [-1, 1]
+fn sample_range(kind: &str) -> (f64, f64) {
+    match kind {
+        "u8" => (0.0, u8::MAX as f64),
+        "u16" => (0.0, u16::MAX as f64),
+        "i16" => (i16::MIN as f64, i16::MAX as f64),
+        "u32" => (0.0, u32::MAX as f64),
+        "i32" => (i32::MIN as f64, i32::MAX as f64),
+        _ => (-1.0, 1.0),
+    }
+}
+
+// 测试信号发送结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TestSignalReport {
+    samples_sent: u64,
+    bytes_sent: usize,
+    elapsed_ms: u64,
+    requested_rate_hz: f64,
+    achieved_rate_hz: f64,
+}
+
+// 按精确的采样率发送测试信号（ramp / sine / square），用于标定串口 ADC 链路
+// 每个采样按 sample_kind/endian 编码（默认 u8），信号频率 signal_hz 默认 1Hz；
+// 按时间表批量发送已到期的采样，避免累计误差
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+fn stream_test_signal(
+    port_name: String,
+    session_id: String,
+    kind: String,
+    sample_rate_hz: f64,
+    duration_ms: u64,
+    sample_kind: Option<String>,
+    endian: Option<String>,
+    signal_hz: Option<f64>,
+) -> Result<TestSignalReport, String> {
+    use std::time::Instant;
+
+    check_session(&port_name, &session_id)?;
+    if !sample_rate_hz.is_finite() || sample_rate_hz <= 0.0 {
+        return Err(format!("Invalid sample rate: {}", sample_rate_hz));
+    }
+    let signal_hz = signal_hz.unwrap_or(1.0);
+    if !signal_hz.is_finite() || signal_hz <= 0.0 {
+        return Err(format!("Invalid signal frequency: {}", signal_hz));
+    }
+    if !matches!(kind.as_str(), "ramp" | "sine" | "square") {
+        return Err(format!("Unsupported signal kind: {}", kind));
+    }
+    let sample_kind = sample_kind.unwrap_or_else(|| "u8".to_string());
+    let little_endian = match endian.as_deref().unwrap_or("le") {
+        "le" => true,
+        "be" => false,
+        other => return Err(format!("Unsupported endian: {}", other)),
+    };
+    encode_one_number(0.0, &sample_kind, little_endian)?;
+
+    let (min, max) = sample_range(&sample_kind);
+    let total_samples = (sample_rate_hz * duration_ms as f64 / 1000.0).round() as u64;
+    let sample_value = |index: u64| {
+        // 信号相位 [0, 1)
+        let phase = (index as f64 * signal_hz / sample_rate_hz).fract();
+        let normalized = match kind.as_str() {
+            "ramp" => phase,
+            "sine" => 0.5 + 0.5 * (phase * std::f64::consts::TAU).sin(),
+            _ => if phase < 0.5 { 1.0 } else { 0.0 },
+        };
+        min + normalized * (max - min)
+    };
+
+    let started = Instant::now();
+    let mut next_index = 0u64;
+    let mut bytes_sent = 0usize;
+    while next_index < total_samples {
+        // 发送截至当前时刻应已发出的所有采样
+        let due = ((started.elapsed().as_secs_f64() * sample_rate_hz) as u64 + 1).min(total_samples);
+        if due > next_index {
+            let mut batch = Vec::new();
+            for index in next_index..due {
+                batch.extend(encode_one_number(sample_value(index), &sample_kind, little_endian)?);
+            }
+            bytes_sent += write_port_bytes(&port_name, &batch, "")?;
+            next_index = due;
+        }
+
+        // 等待下一个采样时刻（最后一个采样之后等满它的采样周期，使耗时对应完整时长）
+        let next_at = started + Duration::from_secs_f64(next_index as f64 / sample_rate_hz);
+        let now = Instant::now();
+        if next_at > now {
+            std::thread::sleep(next_at - now);
+        }
+    }
+
+    let elapsed = started.elapsed();
+    Ok(TestSignalReport {
+        samples_sent: next_index,
+        bytes_sent,
+        elapsed_ms: elapsed.as_millis() as u64,
+        requested_rate_hz: sample_rate_hz,
+        achieved_rate_hz: if elapsed.is_zero() {
+            0.0
+        } else {
+            next_index as f64 / elapsed.as_secs_f64()
+        },
+    })
+}
+
+// 图样发送结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PatternWriteResult {
+    bytes_sent: usize,
+    elapsed_us: u64,
+    bytes_per_second: f64,
+}
+
+// 发送生成的测试图样（线路/线缆测试），返回耗时用于计算吞吐量
+#[tauri::command(async)]
+fn write_pattern(
+    port_name: String,
+    session_id: String,
+    pattern: String,
+    length: usize,
+) -> Result<PatternWriteResult, String> {
+    use std::time::Instant;
+
+    check_session(&port_name, &session_id)?;
+    if length == 0 {
+        return Err("nothing to send".to_string());
+    }
+    let bytes_to_send = generate_pattern(&pattern, length)?;
+
+    let started = Instant::now();
+    let sent = write_port_bytes(&port_name, &bytes_to_send, &pattern)?;
+    // 等待数据真正发出，耗时才反映线路吞吐量
+    wait_for_drain(&port_name, Duration::from_secs(10))?;
+    let elapsed = started.elapsed();
+
+    Ok(PatternWriteResult {
+        bytes_sent: sent,
+        elapsed_us: elapsed.as_micros() as u64,
+        bytes_per_second: sent as f64 / elapsed.as_secs_f64().max(1e-6),
+    })
+}
+
+// 丢弃串口中尚未读取的数据（虚拟或真实）
+fn discard_pending_input(port_name: &str) -> Result<(), String> {
     if port_name.starts_with("VIRTUAL-") {
         let mut buffers = VIRTUAL_BUFFERS.lock()
             .expect("Failed to lock VIRTUAL_BUFFERS mutex");
-        
-        if let Some(buffer) = buffers.get_mut(&port_name) {
-            // 根据不同的虚拟串口类型执行不同操作
-            match port_name.as_str() {
-                "VIRTUAL-COM1" => {
-                    // Echo 模式：原样返回
-                    buffer.extend_from_slice(&bytes_to_send);
-                }
-                "VIRTUAL-COM2" => {
-                    // Reply 模式：返回固定回复
-                    let reply = format!("Received: {}", data);
-                    buffer.extend_from_slice(reply.as_bytes());
+        let buffer = buffers
+            .get_mut(port_name)
+            .ok_or_else(|| format!("Virtual port {} not found", port_name))?;
+        buffer.clear();
+        return Ok(());
+    }
+
+    let port = port_writer(port_name)?;
+    let port = port.lock()
+        .expect("Failed to lock port mutex");
+    port.clear(serialport::ClearBuffer::Input)
+        .map_err(|e| format!("Failed to clear input buffer: {}", e))
+}
+
+// 往返延迟统计结果（单位：微秒）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LatencyStats {
+    min: u64,
+    max: u64,
+    mean: f64,
+    p50: u64,
+    p95: u64,
+    samples: usize,
+    timeouts: usize,
+}
+
+// 测量往返延迟：多次发送探测数据，记录写入完成到收到首字节的时间
+#[tauri::command(async)]
+fn measure_latency(
+    port_name: String,
+    session_id: String,
+    probe: String,
+    is_hex: bool,
+    samples: u32,
+    timeout_ms: Option<u64>,
+) -> Result<LatencyStats, String> {
+    use std::time::Instant;
+
+    check_session(&port_name, &session_id)?;
+    let timeout_ms = resolve_read_timeout(timeout_ms);
+    if samples == 0 {
+        return Err("samples must be greater than 0".to_string());
+    }
+
+    let probe_bytes: Vec<u8> = if is_hex {
+        hex_string_to_bytes(&probe)
+            .map_err(|e| format!("Invalid hex string: {}", e))?
+    } else {
+        probe.as_bytes().to_vec()
+    };
+
+    let mut latencies: Vec<u64> = Vec::with_capacity(samples as usize);
+    let mut timeouts = 0;
+
+    for _ in 0..samples {
+        // 清掉上一轮残留的响应，避免误计时
+        discard_pending_input(&port_name)?;
+        write_port_bytes(&port_name, &probe_bytes, &probe)?;
+        let sent_at = Instant::now();
+        let deadline = sent_at + Duration::from_millis(timeout_ms);
+
+        loop {
+            let data = read_port_chunk(&port_name, deadline)?;
+            if !data.is_empty() {
+                latencies.push(sent_at.elapsed().as_micros() as u64);
+                break;
+            }
+            if Instant::now() >= deadline {
+                timeouts += 1;
+                break;
+            }
+        }
+    }
+
+    if latencies.is_empty() {
+        return Err(format!("No response received in {} samples", samples));
+    }
+
+    latencies.sort_unstable();
+    let percentile = |p: f64| {
+        let rank = (p * latencies.len() as f64).ceil() as usize;
+        latencies[rank.clamp(1, latencies.len()) - 1]
+    };
+
+    Ok(LatencyStats {
+        min: latencies[0],
+        max: latencies[latencies.len() - 1],
+        mean: latencies.iter().sum::<u64>() as f64 / latencies.len() as f64,
+        p50: percentile(0.50),
+        p95: percentile(0.95),
+        samples: latencies.len(),
+        timeouts,
+    })
+}
+
+// 回环测试每次写入的块大小，边写边读，避免接收缓冲溢出
+const LOOPBACK_CHUNK_SIZE: usize = 256;
+
+// 回环测试结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LoopbackReport {
+    success: bool,
+    bytes_sent: usize,
+    bytes_received: usize,
+    // 第一个不一致的字节偏移；长度不一致时为两者中较短的长度
+    mismatch_offset: Option<usize>,
+}
+
+// 回环测试（TX 与 RX 短接）：发送随机图样并读回比对
+// timeout_ms 为每块数据读回的等待时间，省略或为 0 时使用默认读超时
+#[tauri::command(async)]
+fn test_loopback(
+    port_name: String,
+    session_id: String,
+    pattern_len: usize,
+    timeout_ms: Option<u64>,
+) -> Result<LoopbackReport, String> {
+    check_session(&port_name, &session_id)?;
+    if pattern_len == 0 {
+        return Err("pattern_len must be greater than 0".to_string());
+    }
+    let timeout = Duration::from_millis(resolve_read_timeout(timeout_ms));
+    let pattern = pseudo_random_bytes(pattern_len);
+
+    discard_pending_input(&port_name)?;
+    let mut received: Vec<u8> = Vec::with_capacity(pattern_len);
+    let mut bytes_sent = 0;
+    for chunk in pattern.chunks(LOOPBACK_CHUNK_SIZE) {
+        bytes_sent += write_port_bytes(&port_name, chunk, "")?;
+        let deadline = std::time::Instant::now() + timeout;
+        while received.len() < bytes_sent && std::time::Instant::now() < deadline {
+            received.extend(read_port_chunk(&port_name, deadline)?);
+        }
+        if received.len() < bytes_sent {
+            // 本块未完整读回，继续发送意义不大
+            break;
+        }
+    }
+
+    let mismatch_offset = pattern
+        .iter()
+        .zip(&received)
+        .position(|(sent, got)| sent != got)
+        .or(if received.len() != pattern.len() {
+            Some(received.len().min(pattern.len()))
+        } else {
+            None
+        });
+
+    Ok(LoopbackReport {
+        success: mismatch_offset.is_none(),
+        bytes_sent,
+        bytes_received: received.len(),
+        mismatch_offset,
+    })
+}
+
+// 状态线查询的默认超时
+const STATUS_TIMEOUT_MS: u64 = 500;
+
+// 调制解调器状态线
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ModemStatus {
+    cts: bool,
+    dsr: bool,
+    ri: bool,
+    cd: bool,
+}
+
+// 在工作线程中执行状态查询，超时返回 Timeout 错误
+// 部分廉价适配器的状态 ioctl 会卡死：工作线程使用克隆出的独立句柄，
+// 卡住时只泄漏该线程，不会占住端口的读写锁；无法克隆时直接报错，不在可能被遗弃的线程里锁共享句柄
+fn with_status_timeout<T: Send + 'static>(
+    port_name: &str,
+    timeout: Duration,
+    query: impl FnOnce(&mut dyn SerialPort) -> serialport::Result<T> + Send + 'static,
+) -> Result<T, String> {
+    let shared = port_writer(port_name)?;
+    let mut cloned = shared.lock()
+        .expect("Failed to lock port mutex")
+        .try_clone()
+        .map_err(|e| format!(
+            "Status query on {} is unsupported without a cloneable handle: {}",
+            port_name, e
+        ))?;
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(query(cloned.as_mut()));
+    });
+
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => result.map_err(|e| format!("Failed to read modem status: {}", e)),
+        Err(_) => Err(format!(
+            "Timeout: driver did not answer the status query on {} within {} ms",
+            port_name,
+            timeout.as_millis()
+        )),
+    }
+}
+
+// 读取 CTS / DSR / RI / CD 状态线，timeout_ms 内驱动未响应时返回 Timeout 错误
+#[tauri::command(async)]
+fn get_modem_status(
+    port_name: String,
+    session_id: String,
+    timeout_ms: Option<u64>,
+) -> Result<ModemStatus, String> {
+    check_session(&port_name, &session_id)?;
+    modem_status(&port_name, timeout_ms)
+}
+
+// 暂停期间与读写一样不查询状态线
+fn modem_status(port_name: &str, timeout_ms: Option<u64>) -> Result<ModemStatus, String> {
+    check_not_paused(port_name)?;
+    if port_name.starts_with("VIRTUAL-") {
+        if !VIRTUAL_BUFFERS.lock()
+            .expect("Failed to lock VIRTUAL_BUFFERS mutex")
+            .contains_key(port_name)
+        {
+            return Err(format!("Virtual port {} not found", port_name));
+        }
+        // 虚拟串口模拟一条始终就绪的线路
+        return Ok(ModemStatus { cts: true, dsr: true, ri: false, cd: true });
+    }
+
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(STATUS_TIMEOUT_MS));
+    with_status_timeout(port_name, timeout, |port| {
+        Ok(ModemStatus {
+            cts: port.read_clear_to_send()?,
+            dsr: port.read_data_set_ready()?,
+            ri: port.read_ring_indicator()?,
+            cd: port.read_carrier_detect()?,
+        })
+    })
+}
+
+// 状态线位掩码：bit0 CTS、bit1 DSR、bit2 RI、bit3 CD
+const MODEM_BIT_CTS: u8 = 1 << 0;
+const MODEM_BIT_DSR: u8 = 1 << 1;
+const MODEM_BIT_RI: u8 = 1 << 2;
+const MODEM_BIT_CD: u8 = 1 << 3;
+
+// 一次读取所有状态线并打包为位掩码，供高频轮询的状态面板使用
+// Unix 下真实串口只调用一次 TIOCMGET；其它情况逐项查询后打包
+#[tauri::command]
+fn read_modem_bitmask(port_name: String, session_id: String) -> Result<u8, String> {
+    check_session(&port_name, &session_id)?;
+    modem_bitmask(&port_name)
+}
+
+fn modem_bitmask(port_name: &str) -> Result<u8, String> {
+    check_not_paused(port_name)?;
+    #[cfg(unix)]
+    if !port_name.starts_with("VIRTUAL-") && !port_name.starts_with(TCP_PREFIX) {
+        let fd = port_fd(port_name)?;
+        let mut bits: libc::c_int = 0;
+        if unsafe { libc::ioctl(fd, libc::TIOCMGET as _, &mut bits) } != 0 {
+            return Err(format!(
+                "Failed to read modem status: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        let mut mask = 0;
+        for (line, bit) in [
+            (libc::TIOCM_CTS, MODEM_BIT_CTS),
+            (libc::TIOCM_DSR, MODEM_BIT_DSR),
+            (libc::TIOCM_RI, MODEM_BIT_RI),
+            (libc::TIOCM_CD, MODEM_BIT_CD),
+        ] {
+            if bits & line != 0 {
+                mask |= bit;
+            }
+        }
+        return Ok(mask);
+    }
+
+    let status = modem_status(port_name, None)?;
+    Ok([
+        (status.cts, MODEM_BIT_CTS),
+        (status.dsr, MODEM_BIT_DSR),
+        (status.ri, MODEM_BIT_RI),
+        (status.cd, MODEM_BIT_CD),
+    ]
+    .iter()
+    .filter(|(set, _)| *set)
+    .fold(0, |mask, (_, bit)| mask | bit))
+}
+
+// 状态线发生跳变时推送的事件；elapsed_us 为自开始记录起的单调时间，用于比较跳变间隔
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SignalChangeEvent {
+    port_name: String,
+    line: String,
+    state: bool,
+    timestamp_ms: u64,
+    elapsed_us: u64,
+}
+
+// 状态线记录的默认轮询间隔
+const SIGNAL_LOG_POLL_MS: u64 = 10;
+
+// 停止端口的状态线记录（线程在下一次轮询时退出）
+fn cancel_signal_log(port_name: &str) -> bool {
+    match SIGNAL_LOGS.lock()
+        .expect("Failed to lock SIGNAL_LOGS mutex")
+        .remove(port_name)
+    {
+        Some(stop) => {
+            stop.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+// 按 poll_interval_ms 轮询 CTS / DSR / RI / CD，只在某条线跳变时推送 signal-change（每条线一个事件）
+// 跳变时间的精度受轮询间隔限制，间隔内来回跳变的脉冲无法捕获；读取失败时推送 serial-error 并停止。
+// 端口暂停期间停止轮询
+#[tauri::command]
+fn start_signal_log(
+    window: tauri::Window,
+    port_name: String,
+    session_id: String,
+    poll_interval_ms: Option<u64>,
+) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+    let mut previous = modem_bitmask(&port_name)?;
+    let interval = Duration::from_millis(poll_interval_ms.unwrap_or(SIGNAL_LOG_POLL_MS).max(1));
+
+    cancel_signal_log(&port_name);
+    let stop = Arc::new(AtomicBool::new(false));
+    SIGNAL_LOGS.lock()
+        .expect("Failed to lock SIGNAL_LOGS mutex")
+        .insert(port_name.clone(), stop.clone());
+
+    let name = port_name.clone();
+    std::thread::spawn(move || {
+        let started = std::time::Instant::now();
+        while !stop.load(Ordering::Relaxed) {
+            std::thread::sleep(interval);
+            // 暂停期间不查询硬件也不推送事件，恢复后与暂停前的状态比较
+            if is_port_paused(&name) {
+                continue;
+            }
+            let current = match modem_bitmask(&name) {
+                Ok(mask) => mask,
+                Err(e) => {
+                    if !stop.load(Ordering::Relaxed) {
+                        let _ = window.emit("serial-error", SerialErrorEvent {
+                            port_name: name.clone(),
+                            error: e,
+                        });
+                    }
+                    return;
                 }
-                "VIRTUAL-COM3" => {
-                    // Random 模式：返回随机数据
-                    use std::time::{SystemTime, UNIX_EPOCH};
-                    let timestamp = SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_millis();
-                    let random_data = format!("Random-{}", timestamp % 10000);
-                    buffer.extend_from_slice(random_data.as_bytes());
+            };
+            let changed = current ^ previous;
+            if changed == 0 {
+                continue;
+            }
+            let timestamp_ms = unix_millis();
+            let elapsed_us = started.elapsed().as_micros() as u64;
+            for (bit, line) in [
+                (MODEM_BIT_CTS, "cts"),
+                (MODEM_BIT_DSR, "dsr"),
+                (MODEM_BIT_RI, "ri"),
+                (MODEM_BIT_CD, "cd"),
+            ] {
+                if changed & bit != 0 {
+                    let _ = window.emit("signal-change", SignalChangeEvent {
+                        port_name: name.clone(),
+                        line: line.to_string(),
+                        state: current & bit != 0,
+                        timestamp_ms,
+                        elapsed_us,
+                    });
                 }
-                _ => {}
             }
-            
-            return Ok(format!("Sent {} bytes", bytes_to_send.len()));
+            previous = current;
+        }
+    });
+
+    Ok(format!(
+        "Signal log started for {} ({} ms interval)",
+        port_name,
+        interval.as_millis()
+    ))
+}
+
+// 停止记录状态线变化
+#[tauri::command]
+fn stop_signal_log(port_name: String, session_id: String) -> Result<String, String> {
+    check_session(&port_name, &session_id)?;
+
+    if cancel_signal_log(&port_name) {
+        Ok(format!("Signal log for {} stopped", port_name))
+    } else {
+        Err(format!("No signal log running for {}", port_name))
+    }
+}
+
+// 切换串口 raw 模式（Unix termios），返回设置后的实际状态
+#[tauri::command]
+fn set_raw_mode(port_name: String, session_id: String, raw: bool) -> Result<bool, String> {
+    check_session(&port_name, &session_id)?;
+
+    // 虚拟串口和网络串口始终以原始字节传输
+    if port_name.starts_with("VIRTUAL-") || port_name.starts_with(TCP_PREFIX) {
+        return if raw {
+            Ok(true)
+        } else {
+            Err("Virtual and TCP ports only support raw mode".to_string())
+        };
+    }
+
+    #[cfg(unix)]
+    {
+        let fd = port_fd(&port_name)?;
+        set_termios_raw(fd, raw)?;
+        termios_is_raw(fd)
+    }
+
+    #[cfg(not(unix))]
+    {
+        // Windows 串口没有行规程，数据始终是原始字节
+        let ports = SERIAL_PORTS.lock()
+            .expect("Failed to lock SERIAL_PORTS mutex");
+        if !ports.contains_key(&port_name) {
+            return Err(format!("Port {} not found", port_name));
+        }
+        if raw {
+            Ok(true)
+        } else {
+            Err("Cooked mode is not supported on this platform".to_string())
+        }
+    }
+}
+
+// 查询串口当前是否处于 raw 模式
+#[tauri::command]
+fn get_raw_mode(port_name: String, session_id: String) -> Result<bool, String> {
+    check_session(&port_name, &session_id)?;
+
+    if port_name.starts_with("VIRTUAL-") || port_name.starts_with(TCP_PREFIX) {
+        return Ok(true);
+    }
+
+    #[cfg(unix)]
+    {
+        termios_is_raw(port_fd(&port_name)?)
+    }
+
+    #[cfg(not(unix))]
+    {
+        let ports = SERIAL_PORTS.lock()
+            .expect("Failed to lock SERIAL_PORTS mutex");
+        if ports.contains_key(&port_name) {
+            Ok(true)
         } else {
-            return Err(format!("Virtual port {} not found", port_name));
+            Err(format!("Port {} not found", port_name))
         }
     }
-    
-    // 真实串口逻辑
-    let mut ports = SERIAL_PORTS.lock()
-        .expect("Failed to lock SERIAL_PORTS mutex");
-    
-    let port = ports
-        .get_mut(&port_name)
-        .ok_or_else(|| format!("Port {} not found", port_name))?;
+}
 
-    port.write_all(&bytes_to_send)
-        .map_err(|e| format!("Failed to write data: {}", e))?;
+// 获取已打开串口的文件描述符
+#[cfg(unix)]
+fn port_fd(port_name: &str) -> Result<std::os::unix::io::RawFd, String> {
+    let fds = PORT_FDS.lock()
+        .expect("Failed to lock PORT_FDS mutex");
+    fds.get(port_name)
+        .copied()
+        .ok_or_else(|| format!("Port {} not found", port_name))
+}
+
+// 关闭串口时移除文件描述符记录
+fn forget_port_fd(_port_name: &str) {
+    #[cfg(unix)]
+    {
+        let mut fds = PORT_FDS.lock()
+            .expect("Failed to lock PORT_FDS mutex");
+        fds.remove(_port_name);
+    }
+}
+
+// 通过 termios 设置 raw / canonical 模式（保留波特率等设置）
+#[cfg(unix)]
+fn set_termios_raw(fd: std::os::unix::io::RawFd, raw: bool) -> Result<(), String> {
+    let mut termios = read_termios(fd)?;
+    if raw {
+        unsafe { libc::cfmakeraw(&mut termios) };
+    } else {
+        termios.c_iflag |= libc::ICRNL | libc::BRKINT;
+        termios.c_oflag |= libc::OPOST | libc::ONLCR;
+        termios.c_lflag |= libc::ICANON | libc::ECHO | libc::ECHOE | libc::ISIG | libc::IEXTEN;
+    }
+    termios.c_cflag |= libc::CREAD | libc::CLOCAL;
 
-    Ok(format!("Sent {} bytes", bytes_to_send.len()))
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &termios) } != 0 {
+        return Err(format!("tcsetattr failed: {}", std::io::Error::last_os_error()));
+    }
+    Ok(())
 }
 
-// 读取数据
-#[tauri::command]
-fn read_serial_data(port_name: String, timeout_ms: u64) -> Result<Vec<u8>, String> {
-    // 虚拟串口逻辑
-    if port_name.starts_with("VIRTUAL-") {
-        let mut buffers = VIRTUAL_BUFFERS.lock()
-            .expect("Failed to lock VIRTUAL_BUFFERS mutex");
-        
-        if let Some(buffer) = buffers.get_mut(&port_name) {
-            if buffer.is_empty() {
-                return Ok(vec![]);
+// 读取 termios 并判断是否为 raw 模式（无规范处理、无回显、无信号字符、无输出处理）
+#[cfg(unix)]
+fn termios_is_raw(fd: std::os::unix::io::RawFd) -> Result<bool, String> {
+    let termios = read_termios(fd)?;
+    let cooked_lflags = libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN;
+    let cooked_iflags = libc::ICRNL | libc::INLCR | libc::IGNCR | libc::ISTRIP | libc::IXON;
+    Ok(termios.c_lflag & cooked_lflags == 0
+        && termios.c_iflag & cooked_iflags == 0
+        && termios.c_oflag & libc::OPOST == 0)
+}
+
+// 清除 termios 的 HUPCL 标志，关闭端口时不再挂断（拉低 DTR/RTS）
+#[cfg(unix)]
+fn clear_hupcl(fd: std::os::unix::io::RawFd) -> Result<(), String> {
+    let mut termios = read_termios(fd)?;
+    termios.c_cflag &= !libc::HUPCL;
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &termios) } != 0 {
+        return Err(format!("tcsetattr failed: {}", std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn read_termios(fd: std::os::unix::io::RawFd) -> Result<libc::termios, String> {
+    let mut termios = std::mem::MaybeUninit::<libc::termios>::uninit();
+    if unsafe { libc::tcgetattr(fd, termios.as_mut_ptr()) } != 0 {
+        return Err(format!("tcgetattr failed: {}", std::io::Error::last_os_error()));
+    }
+    Ok(unsafe { termios.assume_init() })
+}
+
+// 数值类型的字节宽度
+fn number_width(kind: &str) -> Result<usize, String> {
+    match kind {
+        "u8" => Ok(1),
+        "u16" | "i16" => Ok(2),
+        "u32" | "i32" | "f32" => Ok(4),
+        "f64" => Ok(8),
+        other => Err(format!("Unsupported number kind: {}", other)),
+    }
+}
+
+// 按类型和字节序解析单个数值
+fn decode_one_number(chunk: &[u8], kind: &str, little_endian: bool) -> f64 {
+    macro_rules! parse {
+        ($t:ty) => {{
+            let bytes = chunk.try_into().expect("chunk width matches type");
+            if little_endian {
+                <$t>::from_le_bytes(bytes) as f64
+            } else {
+                <$t>::from_be_bytes(bytes) as f64
             }
-            
-            // 读取所有缓冲数据
-            let data = buffer.clone();
-            buffer.clear();
-            return Ok(data);
-        } else {
-            return Err(format!("Virtual port {} not found", port_name));
-        }
+        }};
     }
-    
-    // 真实串口逻辑
-    let mut ports = SERIAL_PORTS.lock()
-        .expect("Failed to lock SERIAL_PORTS mutex");
-    
-    let port = ports
-        .get_mut(&port_name)
-        .ok_or_else(|| format!("Port {} not found", port_name))?;
+    match kind {
+        "u8" => chunk[0] as f64,
+        "u16" => parse!(u16),
+        "i16" => parse!(i16),
+        "u32" => parse!(u32),
+        "i32" => parse!(i32),
+        "f32" => parse!(f32),
+        _ => parse!(f64),
+    }
+}
 
-    // 设置超时
-    port.set_timeout(Duration::from_millis(timeout_ms))
-        .map_err(|e| format!("Failed to set timeout: {}", e))?;
+// 将字节按指定类型解析为数值数组，长度必须是类型宽度的整数倍
+fn decode_numbers(bytes: &[u8], kind: &str, endian: &str) -> Result<Vec<f64>, String> {
+    let width = number_width(kind)?;
+    let little_endian = match endian {
+        "le" => true,
+        "be" => false,
+        other => return Err(format!("Unsupported endian: {}", other)),
+    };
+    if bytes.len() % width != 0 {
+        return Err(format!(
+            "Length mismatch: {} bytes is not a multiple of {} ({})",
+            bytes.len(),
+            width,
+            kind
+        ));
+    }
 
-    let mut buffer: Vec<u8> = vec![0; 1024];
-    match port.read(&mut buffer) {
-        Ok(n) => {
-            buffer.truncate(n);
-            Ok(buffer)
-        }
-        Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => {
-            Ok(vec![])
-        }
-        Err(e) => Err(format!("Failed to read data: {}", e)),
+    Ok(bytes
+        .chunks_exact(width)
+        .map(|chunk| decode_one_number(chunk, kind, little_endian))
+        .collect())
+}
+
+// 读取一次数据并按采样类型解析为数值，末尾不完整的采样留到下次调用拼接
+// 采样类型改变时丢弃之前的剩余字节，避免错位
+#[tauri::command]
+fn read_samples(
+    port_name: String,
+    session_id: String,
+    sample_kind: String,
+    endian: String,
+    timeout_ms: Option<u64>,
+) -> Result<Vec<f64>, String> {
+    check_session(&port_name, &session_id)?;
+    let timeout_ms = resolve_read_timeout(timeout_ms);
+    let width = number_width(&sample_kind)?;
+    // 读取前先校验参数，避免读走的数据因参数错误而丢失
+    decode_numbers(&[], &sample_kind, &endian)?;
+    let data = read_port_bytes(&port_name, timeout_ms)?;
+
+    let mut remainders = SAMPLE_REMAINDERS.lock()
+        .expect("Failed to lock SAMPLE_REMAINDERS mutex");
+    let mut bytes = match remainders.remove(&port_name) {
+        Some((kind, rest)) if kind == sample_kind => rest,
+        _ => Vec::new(),
+    };
+    bytes.extend_from_slice(&data);
+
+    let complete = bytes.len() - bytes.len() % width;
+    let rest = bytes.split_off(complete);
+    if !rest.is_empty() {
+        remainders.insert(port_name, (sample_kind.clone(), rest));
     }
+    drop(remainders);
+
+    decode_numbers(&bytes, &sample_kind, &endian)
+}
+
+// 将接收到的原始字节解析为数值（u8/u16/u32/i16/i32/f32/f64，le/be）
+#[tauri::command]
+fn decode_number(bytes: Vec<u8>, kind: String, endian: String) -> Result<Vec<f64>, String> {
+    decode_numbers(&bytes, &kind, &endian)
 }
 
 // HEX字符串转字节数组
@@ -297,6 +5951,408 @@ fn hex_string_to_bytes(hex: &str) -> Result<Vec<u8>, String> {
         .collect()
 }
 
+// 按字（16/32 位）翻转字节序，长度必须是字长的整数倍
+fn apply_byte_order(mut bytes: Vec<u8>, byte_order: &str) -> Result<Vec<u8>, String> {
+    let word_size = match byte_order {
+        "none" | "" => return Ok(bytes),
+        "swap16" => 2,
+        "swap32" => 4,
+        other => return Err(format!("Unsupported byte order: {}", other)),
+    };
+    if bytes.len() % word_size != 0 {
+        return Err(format!(
+            "Length {} is not a multiple of the word size {}",
+            bytes.len(),
+            word_size
+        ));
+    }
+    for word in bytes.chunks_exact_mut(word_size) {
+        word.reverse();
+    }
+    Ok(bytes)
+}
+
+// 串口访问权限检查结果；无法判断时 readable/writable 为 None
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PortPermission {
+    readable: Option<bool>,
+    writable: Option<bool>,
+    hint: Option<String>,
+}
+
+// 检查当前用户能否打开指定串口，并给出可操作的修复建议
+#[tauri::command]
+fn check_port_permission(port_name: String) -> Result<PortPermission, String> {
+    if port_name.starts_with("VIRTUAL-") || port_name.starts_with(TCP_PREFIX) {
+        return Ok(PortPermission {
+            readable: Some(true),
+            writable: Some(true),
+            hint: None,
+        });
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let metadata = std::fs::metadata(&port_name)
+            .map_err(|e| format!("Failed to stat {}: {}", port_name, e))?;
+        let readable = unix_access(&port_name, libc::R_OK);
+        let writable = unix_access(&port_name, libc::W_OK);
+
+        let hint = if readable && writable {
+            None
+        } else {
+            let group = unix_group_name(metadata.gid())
+                .unwrap_or_else(|| metadata.gid().to_string());
+            Some(format!(
+                "{} is owned by group '{}' (mode {:o}). Add your user to it with \
+                 `sudo usermod -aG {} $USER`, then log out and back in",
+                port_name,
+                group,
+                metadata.mode() & 0o777,
+                group
+            ))
+        };
+
+        Ok(PortPermission {
+            readable: Some(readable),
+            writable: Some(writable),
+            hint,
+        })
+    }
+
+    #[cfg(not(unix))]
+    {
+        // Windows 没有设备文件权限，打开失败通常是端口被其它程序占用
+        Ok(PortPermission {
+            readable: None,
+            writable: None,
+            hint: Some(format!(
+                "Permission check is unknown on this platform; if {} fails to open, \
+                 make sure no other program is using it",
+                port_name
+            )),
+        })
+    }
+}
+
+// 以当前用户身份检查文件访问权限
+#[cfg(unix)]
+fn unix_access(path: &str, mode: libc::c_int) -> bool {
+    match std::ffi::CString::new(path) {
+        Ok(path) => unsafe { libc::access(path.as_ptr(), mode) == 0 },
+        Err(_) => false,
+    }
+}
+
+// 查询 gid 对应的组名
+#[cfg(unix)]
+fn unix_group_name(gid: u32) -> Option<String> {
+    let mut group: libc::group = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    let mut result: *mut libc::group = std::ptr::null_mut();
+    let rc = unsafe {
+        libc::getgrgid_r(gid, &mut group, buffer.as_mut_ptr(), buffer.len(), &mut result)
+    };
+    if rc != 0 || result.is_null() {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(group.gr_name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+// FTDI 芯片 latency_timer 的 sysfs 路径（仅 Linux ftdi_sio 驱动提供）
+#[cfg(target_os = "linux")]
+fn ftdi_latency_path(port_name: &str) -> Result<std::path::PathBuf, String> {
+    if port_name.starts_with("VIRTUAL-") || port_name.starts_with(TCP_PREFIX) {
+        return Err(format!("Port {} is not an FTDI adapter", port_name));
+    }
+    // 支持 /dev/ttyUSB0 或 ttyUSB0，跟随符号链接（如 /dev/serial/by-id/...）
+    let device = std::fs::canonicalize(port_name)
+        .unwrap_or_else(|_| std::path::PathBuf::from(port_name));
+    let tty = device
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| format!("Invalid port name: {}", port_name))?;
+
+    let path = std::path::Path::new("/sys/bus/usb-serial/devices")
+        .join(tty)
+        .join("latency_timer");
+    if !path.exists() {
+        return Err(format!(
+            "Port {} is not an FTDI adapter (no latency_timer in sysfs)",
+            port_name
+        ));
+    }
+    Ok(path)
+}
+
+// 读取 FTDI 适配器的 latency timer（毫秒）
+#[tauri::command]
+fn get_ftdi_latency(port_name: String) -> Result<u8, String> {
+    #[cfg(target_os = "linux")]
+    {
+        let path = ftdi_latency_path(&port_name)?;
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        content
+            .trim()
+            .parse::<u8>()
+            .map_err(|e| format!("Invalid latency value '{}': {}", content.trim(), e))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        Err(format!("FTDI latency control for {} is only supported on Linux", port_name))
+    }
+}
+
+// 设置 FTDI 适配器的 latency timer（1~255 毫秒，默认 16），返回回读的值
+// 写入 sysfs 通常需要 root 权限或相应的 udev 规则
+#[tauri::command]
+fn set_ftdi_latency(port_name: String, ms: u8) -> Result<u8, String> {
+    if ms == 0 {
+        return Err("Latency must be between 1 and 255 ms".to_string());
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let path = ftdi_latency_path(&port_name)?;
+        std::fs::write(&path, ms.to_string())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        get_ftdi_latency(port_name)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        Err(format!("FTDI latency control for {} is only supported on Linux", port_name))
+    }
+}
+
+// 查找串口所属 USB 设备的 usbfs 节点（/dev/bus/usb/BBB/DDD）
+#[cfg(target_os = "linux")]
+fn usb_device_node(port_name: &str) -> Result<std::path::PathBuf, String> {
+    if port_name.starts_with("VIRTUAL-") || port_name.starts_with(TCP_PREFIX) {
+        return Err(format!("Port {} is not a USB device", port_name));
+    }
+    let device = std::fs::canonicalize(port_name)
+        .unwrap_or_else(|_| std::path::PathBuf::from(port_name));
+    let tty = device
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| format!("Invalid port name: {}", port_name))?;
+
+    // 从 tty 设备向上查找带 busnum/devnum 的 USB 设备目录
+    let sys_device = std::fs::canonicalize(
+        std::path::Path::new("/sys/class/tty").join(tty).join("device"),
+    )
+    .map_err(|_| format!("Port {} has no backing device in sysfs", port_name))?;
+    let read_number = |dir: &std::path::Path, name: &str| -> Option<u32> {
+        std::fs::read_to_string(dir.join(name)).ok()?.trim().parse().ok()
+    };
+    for dir in sys_device.ancestors() {
+        if let (Some(bus), Some(dev)) = (read_number(dir, "busnum"), read_number(dir, "devnum")) {
+            return Ok(std::path::PathBuf::from(format!("/dev/bus/usb/{:03}/{:03}", bus, dev)));
+        }
+    }
+    Err(format!("Port {} is not backed by a USB device", port_name))
+}
+
+// 复位串口所属的 USB 设备（Linux USBDEVFS_RESET），设备会重新枚举
+// 端口已被本应用打开时必须由持有者会话调用，复位前先关闭该端口；其它会话持有时拒绝复位。
+// 访问 usbfs 节点通常需要 root 权限或 udev 规则
+#[tauri::command(async)]
+fn usb_reset(port_name: String, session_id: Option<String>) -> Result<String, String> {
+    if is_port_claimed(&port_name) {
+        check_session(&port_name, session_id.as_deref().unwrap_or_default())?;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        // _IO('U', 20)
+        const USBDEVFS_RESET: libc::c_ulong = 0x5514;
+
+        let node = usb_device_node(&port_name)?;
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&node)
+            .map_err(|e| format!("Failed to open {}: {}", node.display(), e))?;
+
+        let was_open = close_port(&port_name);
+        if unsafe { libc::ioctl(file.as_raw_fd(), USBDEVFS_RESET as _, 0) } != 0 {
+            return Err(format!(
+                "USB reset of {} failed: {}",
+                node.display(),
+                std::io::Error::last_os_error()
+            ));
+        }
+        log_message(LogLevel::Info, format!("已复位 USB 设备 {} ({})", node.display(), port_name));
+
+        Ok(if was_open {
+            format!("USB device {} reset; port {} was closed and must be reopened", node.display(), port_name)
+        } else {
+            format!("USB device {} reset", node.display())
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        Err(format!("USB reset for {} is only supported on Linux", port_name))
+    }
+}
+
+// 帧校验结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FrameValidation {
+    valid: bool,
+    reason: Option<String>,
+}
+
+// 按声明的格式（起始字节、长度、校验类型）校验一帧数据
+#[tauri::command]
+fn validate_frame(bytes: Vec<u8>, schema: FrameSchema) -> Result<FrameValidation, String> {
+    // 不支持的校验类型属于调用错误，直接返回 Err
+    if let Some(kind) = &schema.checksum {
+        frame::checksum_bytes(kind, &[])?;
+    }
+
+    Ok(match frame::validate_frame(&bytes, &schema) {
+        Ok(()) => FrameValidation { valid: true, reason: None },
+        Err(reason) => FrameValidation { valid: false, reason: Some(reason) },
+    })
+}
+
+// 单个字节在数据中出现的位置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ByteOccurrences {
+    byte: u8,
+    offsets: Vec<usize>,
+}
+
+// 查找每个指定字节（如帧分隔符 0x7E）在数据中出现的全部偏移，按 needles 顺序返回，重复的 needle 只返回一次
+#[tauri::command]
+fn scan_for_bytes(bytes: Vec<u8>, needles: Vec<u8>) -> Vec<ByteOccurrences> {
+    let mut results: Vec<ByteOccurrences> = Vec::new();
+    for needle in needles {
+        if results.iter().any(|r| r.byte == needle) {
+            continue;
+        }
+        results.push(ByteOccurrences {
+            byte: needle,
+            offsets: bytes
+                .iter()
+                .enumerate()
+                .filter(|(_, &b)| b == needle)
+                .map(|(offset, _)| offset)
+                .collect(),
+        });
+    }
+    results
+}
+
+// 两段数据同一偏移处的差异；超出较短一方长度的部分对应字节为 None
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ByteDiff {
+    offset: usize,
+    a_byte: Option<u8>,
+    b_byte: Option<u8>,
+}
+
+// 按偏移逐字节比较两段捕获数据（不做插入/删除对齐），长度不同时多出的字节逐个列出
+#[tauri::command]
+fn diff_captures(a: Vec<u8>, b: Vec<u8>) -> Vec<ByteDiff> {
+    (0..a.len().max(b.len()))
+        .filter_map(|offset| {
+            let (a_byte, b_byte) = (a.get(offset).copied(), b.get(offset).copied());
+            (a_byte != b_byte).then_some(ByteDiff { offset, a_byte, b_byte })
+        })
+        .collect()
+}
+
+// 估算发送 byte_count 字节所需的时间（毫秒，向上取整）
+// 每字节位数 = 起始位 1 + 数据位 + 校验位（None 为 0）+ 停止位
+#[tauri::command]
+fn estimate_tx_time(
+    byte_count: u64,
+    baud_rate: u32,
+    data_bits: u8,
+    stop_bits: u8,
+    parity: String,
+) -> Result<u64, String> {
+    if baud_rate == 0 {
+        return Err("Baud rate must be greater than 0".to_string());
+    }
+    if !(5..=8).contains(&data_bits) {
+        return Err(format!("Unsupported data bits: {}", data_bits));
+    }
+    if !(1..=2).contains(&stop_bits) {
+        return Err(format!("Unsupported stop bits: {}", stop_bits));
+    }
+    let parity_bits = match parity.as_str() {
+        "None" => 0,
+        "Odd" | "Even" => 1,
+        other => return Err(format!("Unsupported parity: {}", other)),
+    };
+
+    let bits_per_byte = 1 + data_bits as u64 + parity_bits + stop_bits as u64;
+    let total_bits = byte_count.saturating_mul(bits_per_byte);
+    Ok(total_bits.saturating_mul(1000).div_ceil(baud_rate as u64))
+}
+
+// 常见标准波特率
+const STANDARD_BAUD_RATES: [u32; 21] = [
+    300, 600, 1200, 2400, 4800, 9600, 14400, 19200, 28800, 38400, 57600,
+    76800, 115200, 230400, 250000, 460800, 500000, 921600, 1000000, 1500000, 2000000,
+];
+
+// 波特率推测结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BaudSuggestion {
+    baud_rate: u32,
+    // 测得波特率相对标准值的偏差百分比（正数表示实测偏快）
+    error_percent: f64,
+}
+
+// 根据测得的单个位时长（纳秒）推测最接近的标准波特率
+#[tauri::command]
+fn suggest_baud_from_bit_time(nanoseconds_per_bit: f64) -> Result<BaudSuggestion, String> {
+    if !nanoseconds_per_bit.is_finite() || nanoseconds_per_bit <= 0.0 {
+        return Err(format!("Invalid bit time: {} ns", nanoseconds_per_bit));
+    }
+
+    let measured = 1e9 / nanoseconds_per_bit;
+    let error_of = |baud: u32| (measured - baud as f64) / baud as f64 * 100.0;
+    let baud_rate = STANDARD_BAUD_RATES
+        .iter()
+        .copied()
+        .min_by(|a, b| error_of(*a).abs().total_cmp(&error_of(*b).abs()))
+        .expect("standard baud rate table is not empty");
+
+    Ok(BaudSuggestion {
+        baud_rate,
+        error_percent: error_of(baud_rate),
+    })
+}
+
+// 解析 HEX 字符串并按需调整字节序
+#[tauri::command]
+fn parse_hex(hex: String, byte_order: Option<String>) -> Result<Vec<u8>, String> {
+    let bytes = hex_string_to_bytes(&hex)
+        .map_err(|e| format!("Invalid hex string: {}", e))?;
+    apply_byte_order(bytes, byte_order.as_deref().unwrap_or("none"))
+}
+
+// 字节数组转空格分隔的大写 HEX 字符串
+fn bytes_to_hex_string(bytes: &[u8]) -> String {
+    bytes.iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 // Windows 串口路径规范化函数
 // Windows 上 COM10 及以上需要使用 \\.\COMx 格式
 fn normalize_port_path(port_name: &str) -> String {
@@ -332,8 +6388,346 @@ pub fn run() {
             open_serial_port,
             close_serial_port,
             write_serial_data,
-            read_serial_data
+            read_serial_data,
+            set_raw_mode,
+            get_raw_mode,
+            measure_latency,
+            set_virtual_responses,
+            read_serial_base64,
+            write_serial_base64,
+            reset_port_defaults,
+            terminal_feed,
+            expect_string,
+            at_command,
+            start_serial_listener,
+            stop_serial_listener,
+            rename_virtual_port,
+            byte_histogram,
+            write_9bit,
+            register_frame_parser,
+            unregister_frame_parser,
+            replay_hex_file,
+            list_available_ports,
+            decode_number,
+            check_connection,
+            parse_hex,
+            read_until_regex,
+            preview_write,
+            write_then_listen,
+            get_serial_stats,
+            write_pattern,
+            set_virtual_mode,
+            read_exact_serial,
+            export_capture_csv,
+            enable_auto_reconnect,
+            close_serial_port_quiet,
+            timing_report,
+            send_dmx_frame,
+            set_log_level,
+            get_recent_logs,
+            suggest_baud_from_bit_time,
+            get_ftdi_latency,
+            set_ftdi_latency,
+            read_serial_dual,
+            create_pty,
+            close_all_ports,
+            get_read_timeout,
+            validate_frame,
+            write_serial_hex_stream,
+            read_samples,
+            sync_to_idle,
+            estimate_tx_time,
+            start_serial_channel,
+            set_virtual_fault,
+            check_port_permission,
+            read_serial_multi,
+            debug_snapshot,
+            open_and_listen,
+            get_modem_status,
+            stream_test_signal,
+            read_available,
+            rs485_transaction,
+            export_virtual_scenario,
+            import_virtual_scenario,
+            write_serial_chunked,
+            set_virtual_ports_visible,
+            start_stream_checksum,
+            finalize_stream_checksum,
+            usb_reset,
+            ymodem_send,
+            ymodem_receive,
+            suggest_read_chunk,
+            #[cfg(feature = "test-inject")]
+            inject_read,
+            write_multidrop,
+            read_modem_bitmask,
+            restart_listener,
+            open_nth_of_type,
+            set_default_timeout,
+            scan_for_bytes,
+            test_loopback,
+            pause_port,
+            resume_port,
+            virtual_expected_response,
+            set_auto_close,
+            read_response,
+            bitbang_write,
+            diff_captures,
+            start_signal_log,
+            stop_signal_log,
+            get_last_error
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_tx_time_8n1() {
+        // 每字节 10 位：1000 字节 @ 9600 = 1041.67ms，向上取整
+        assert_eq!(estimate_tx_time(1000, 9600, 8, 1, "None".to_string()), Ok(1042));
+        assert_eq!(estimate_tx_time(960, 9600, 8, 1, "None".to_string()), Ok(1000));
+    }
+
+    #[test]
+    fn estimate_tx_time_7e2() {
+        // 每字节 11 位：100 字节 @ 1200 = 916.67ms
+        assert_eq!(estimate_tx_time(100, 1200, 7, 2, "Even".to_string()), Ok(917));
+    }
+
+    #[test]
+    fn estimate_tx_time_rejects_zero_baud() {
+        assert!(estimate_tx_time(10, 0, 8, 1, "None".to_string()).is_err());
+    }
+
+    #[test]
+    fn estimate_tx_time_rounds_sub_millisecond_up() {
+        // 1 字节 @ 115200 约 0.087ms
+        assert_eq!(estimate_tx_time(1, 115200, 8, 1, "None".to_string()), Ok(1));
+        assert_eq!(estimate_tx_time(0, 115200, 8, 1, "None".to_string()), Ok(0));
+    }
+
+    #[test]
+    fn validate_frame_reports_reason() {
+        let schema = FrameSchema {
+            start_byte: Some(0x02),
+            length: None,
+            checksum: Some("xor8".to_string()),
+        };
+        let ok = validate_frame(vec![0x02, 0x10, 0x12], schema.clone()).unwrap();
+        assert!(ok.valid && ok.reason.is_none());
+        let bad = validate_frame(vec![0x02, 0x10, 0x00], schema).unwrap();
+        assert!(!bad.valid && bad.reason.unwrap().starts_with("checksum mismatch"));
+
+        // 不支持的校验类型是调用错误
+        let unsupported = FrameSchema { checksum: Some("md5".to_string()), ..Default::default() };
+        assert!(validate_frame(vec![0x02], unsupported).is_err());
+    }
+
+    #[test]
+    fn scan_for_bytes_lists_offsets_once_per_needle() {
+        let results = scan_for_bytes(vec![0x7E, 0x01, 0x7E, 0x02], vec![0x7E, 0x03, 0x7E]);
+        assert_eq!(results.len(), 2);
+        assert_eq!((results[0].byte, results[0].offsets.clone()), (0x7E, vec![0, 2]));
+        assert_eq!((results[1].byte, results[1].offsets.clone()), (0x03, vec![]));
+    }
+
+    #[test]
+    fn diff_captures_aligned_and_length_mismatch() {
+        assert!(diff_captures(vec![1, 2, 3], vec![1, 2, 3]).is_empty());
+
+        let diffs = diff_captures(vec![1, 9, 3], vec![1, 2, 3, 4]);
+        let summary: Vec<_> = diffs.iter().map(|d| (d.offset, d.a_byte, d.b_byte)).collect();
+        assert_eq!(summary, vec![(1, Some(9), Some(2)), (3, None, Some(4))]);
+
+        let diffs = diff_captures(vec![5, 6], vec![]);
+        let summary: Vec<_> = diffs.iter().map(|d| (d.offset, d.a_byte, d.b_byte)).collect();
+        assert_eq!(summary, vec![(0, Some(5), None), (1, Some(6), None)]);
+    }
+
+    #[test]
+    fn suggest_baud_picks_nearest_standard_rate() {
+        // 9600 波特每位约 104166.7ns，实测偏慢 1%
+        let suggestion = suggest_baud_from_bit_time(1e9 / 9504.0).unwrap();
+        assert_eq!(suggestion.baud_rate, 9600);
+        assert!((suggestion.error_percent + 1.0).abs() < 1e-9);
+
+        assert_eq!(suggest_baud_from_bit_time(1e9 / 115200.0).unwrap().baud_rate, 115200);
+        assert!(suggest_baud_from_bit_time(0.0).is_err());
+        assert!(suggest_baud_from_bit_time(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn decode_number_kinds_and_endianness() {
+        assert_eq!(decode_number(vec![0x34, 0x12], "u16".into(), "le".into()), Ok(vec![4660.0]));
+        assert_eq!(decode_number(vec![0x12, 0x34], "u16".into(), "be".into()), Ok(vec![4660.0]));
+        assert_eq!(decode_number(vec![0xFF, 0xFE], "i16".into(), "be".into()), Ok(vec![-2.0]));
+        assert_eq!(
+            decode_number(1.5f32.to_le_bytes().to_vec(), "f32".into(), "le".into()),
+            Ok(vec![1.5])
+        );
+        assert!(decode_number(vec![1, 2, 3], "u16".into(), "le".into()).is_err());
+        assert!(decode_number(vec![1, 2], "u16".into(), "middle".into()).is_err());
+        assert!(decode_number(vec![1], "u128".into(), "le".into()).is_err());
+    }
+
+    #[test]
+    fn apply_byte_order_swaps_words() {
+        assert_eq!(apply_byte_order(vec![1, 2, 3, 4], "none"), Ok(vec![1, 2, 3, 4]));
+        assert_eq!(apply_byte_order(vec![1, 2, 3, 4], "swap16"), Ok(vec![2, 1, 4, 3]));
+        assert_eq!(apply_byte_order(vec![1, 2, 3, 4], "swap32"), Ok(vec![4, 3, 2, 1]));
+        assert!(apply_byte_order(vec![1, 2, 3], "swap16").is_err());
+        assert!(apply_byte_order(vec![1, 2], "swap64").is_err());
+    }
+
+    fn length_field(offset: usize, size: usize, includes_self: bool, endian: &str) -> LengthField {
+        LengthField { offset, size, includes_self, endian: Some(endian.to_string()) }
+    }
+
+    #[test]
+    fn insert_length_field_encodes_length() {
+        let mut bytes = vec![0xAA, 1, 2, 3];
+        insert_length_field(&mut bytes, &length_field(1, 2, false, "be")).unwrap();
+        assert_eq!(bytes, vec![0xAA, 0x00, 0x03, 1, 2, 3]);
+
+        let mut bytes = vec![1, 2, 3];
+        insert_length_field(&mut bytes, &length_field(0, 2, true, "le")).unwrap();
+        assert_eq!(bytes, vec![0x05, 0x00, 1, 2, 3]);
+    }
+
+    #[test]
+    fn insert_length_field_rejects_invalid_fields() {
+        let mut bytes = vec![0u8; 256];
+        assert!(insert_length_field(&mut bytes, &length_field(0, 1, false, "be")).is_err());
+        assert!(insert_length_field(&mut bytes, &length_field(0, 3, false, "be")).is_err());
+        assert!(insert_length_field(&mut bytes, &length_field(300, 2, false, "be")).is_err());
+        assert!(insert_length_field(&mut bytes, &length_field(0, 2, false, "middle")).is_err());
+        assert_eq!(bytes.len(), 256);
+    }
+
+    fn open_virtual(port_name: &str) -> String {
+        let config: SerialConfig = serde_json::from_value(serde_json::json!({
+            "port_name": port_name,
+            "baud_rate": 115200,
+            "data_bits": 8,
+            "stop_bits": 1,
+            "parity": "none",
+        }))
+        .unwrap();
+        open_serial_port(config).unwrap().session_id
+    }
+
+    #[test]
+    fn port_commands_require_the_owning_session() {
+        let port = "VIRTUAL-SESSION-TEST";
+        let session_id = open_virtual(port);
+        let other = "other".to_string();
+
+        assert!(pause_port(port.to_string(), other.clone()).is_err());
+        assert!(!is_port_paused(port));
+        assert!(read_modem_bitmask(port.to_string(), other.clone()).is_err());
+        assert!(get_serial_stats(port.to_string(), other.clone()).is_err());
+        assert!(reset_port_defaults(port.to_string(), other).is_err());
+
+        assert!(pause_port(port.to_string(), session_id.clone()).is_ok());
+        let err = read_modem_bitmask(port.to_string(), session_id.clone()).unwrap_err();
+        assert!(err.starts_with("Paused"), "{}", err);
+        assert!(get_modem_status(port.to_string(), session_id.clone(), None).is_err());
+        assert!(resume_port(port.to_string(), session_id.clone()).is_ok());
+        assert!(read_modem_bitmask(port.to_string(), session_id.clone()).is_ok());
+        assert!(get_serial_stats(port.to_string(), session_id.clone()).is_ok());
+
+        assert!(close_port(port));
+        assert!(get_serial_stats(port.to_string(), session_id).is_err());
+    }
+
+    #[test]
+    fn usb_reset_refuses_ports_owned_by_another_session() {
+        let port = "VIRTUAL-USB-RESET-TEST";
+        let session_id = open_virtual(port);
+
+        let err = usb_reset(port.to_string(), Some("other".to_string())).unwrap_err();
+        assert!(err.contains("another session"), "{}", err);
+        assert!(usb_reset(port.to_string(), None).is_err());
+        assert!(check_session(port, &session_id).is_ok());
+
+        assert!(close_port(port));
+    }
+
+    #[test]
+    fn releasing_a_port_stops_its_auto_close_timer() {
+        let port = "VIRTUAL-AUTO-CLOSE-TEST";
+        open_virtual(port);
+        let stop = Arc::new(AtomicBool::new(false));
+        AUTO_CLOSE.lock().unwrap().insert(port.to_string(), stop.clone());
+
+        assert!(close_port(port));
+        assert!(stop.load(Ordering::Relaxed));
+        assert!(!AUTO_CLOSE.lock().unwrap().contains_key(port));
+    }
+
+    #[test]
+    fn bitbang_bits_are_not_counted_as_sent_bytes() {
+        let port = "VIRTUAL-BITBANG-STATS-TEST";
+        let session_id = open_virtual(port);
+        record_bitbang(port, 10);
+
+        let stats = get_serial_stats(port.to_string(), session_id).unwrap();
+        assert_eq!(stats.bitbang_bits, 10);
+        assert_eq!(stats.bytes_sent, 0);
+        assert!(CAPTURES.lock().unwrap().get(port).is_some_and(|c| c.is_empty()));
+
+        assert!(close_port(port));
+    }
+
+    #[test]
+    fn rename_virtual_port_moves_per_port_state() {
+        let old_name = "VIRTUAL-RENAME-OLD";
+        let new_name = "VIRTUAL-RENAME-NEW";
+        let session_id = open_virtual(old_name);
+        record_tx(old_name, b"abc");
+        PAUSED_PORTS.lock().unwrap().insert(old_name.to_string());
+
+        rename_virtual_port(old_name.to_string(), new_name.to_string()).unwrap();
+
+        let stats = get_serial_stats(new_name.to_string(), session_id).unwrap();
+        assert_eq!(stats.bytes_sent, 3);
+        assert!(!PORT_STATS.lock().unwrap().contains_key(old_name));
+        assert!(CAPTURES.lock().unwrap().get(new_name).is_some_and(|c| c.len() == 1));
+        assert!(!CAPTURES.lock().unwrap().contains_key(old_name));
+        assert!(is_port_paused(new_name));
+        assert!(!is_port_paused(old_name));
+
+        assert!(close_port(new_name));
+    }
+
+    #[test]
+    fn rename_virtual_port_refuses_while_auto_close_is_active() {
+        let old_name = "VIRTUAL-RENAME-TIMER";
+        open_virtual(old_name);
+        AUTO_CLOSE.lock().unwrap()
+            .insert(old_name.to_string(), Arc::new(AtomicBool::new(false)));
+
+        let err = rename_virtual_port(old_name.to_string(), "VIRTUAL-RENAME-TIMER-2".to_string())
+            .unwrap_err();
+        assert!(err.contains("auto-close"), "{}", err);
+
+        assert!(close_port(old_name));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn create_pty_registers_fd_and_session() {
+        let pty = create_pty().unwrap();
+        assert!(port_fd(&pty.handle_id).is_ok());
+        assert!(check_session(&pty.handle_id, &pty.session_id).is_ok());
+        assert!(check_session(&pty.handle_id, "other").is_err());
+
+        assert!(close_port(&pty.handle_id));
+        assert!(port_fd(&pty.handle_id).is_err());
+        assert!(check_session(&pty.handle_id, &pty.session_id).is_err());
+    }
+}
@@ -0,0 +1,352 @@
+// TCP 串口服务器适配（raw socket 模式）
+// 将 TcpStream 包装为 SerialPort，使读写/关闭命令对网络串口透明
+use serialport::{
+    ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits,
+};
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+// 端口名前缀，例如 tcp://192.168.1.10:4001
+pub const TCP_PREFIX: &str = "tcp://";
+
+// 连接超时
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct TcpSerialPort {
+    stream: TcpStream,
+    name: String,
+    timeout: Duration,
+    // raw socket 无法下发串口参数，这里仅记录，供查询时回显
+    baud_rate: u32,
+    data_bits: DataBits,
+    flow_control: FlowControl,
+    parity: Parity,
+    stop_bits: StopBits,
+}
+
+impl TcpSerialPort {
+    // 连接 tcp://host:port 形式的端口
+    pub fn connect(port_name: &str, baud_rate: u32, timeout: Duration) -> Result<Self, String> {
+        let address = port_name
+            .strip_prefix(TCP_PREFIX)
+            .ok_or_else(|| format!("Invalid TCP port name: {}", port_name))?;
+
+        let addrs = address
+            .to_socket_addrs()
+            .map_err(|e| format!("Failed to resolve {}: {}", address, e))?;
+
+        let mut last_error = format!("No address resolved for {}", address);
+        for addr in addrs {
+            match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+                Ok(stream) => {
+                    stream.set_nodelay(true)
+                        .map_err(|e| format!("Failed to set TCP_NODELAY: {}", e))?;
+                    let mut port = TcpSerialPort {
+                        stream,
+                        name: port_name.to_string(),
+                        timeout,
+                        baud_rate,
+                        data_bits: DataBits::Eight,
+                        flow_control: FlowControl::None,
+                        parity: Parity::None,
+                        stop_bits: StopBits::One,
+                    };
+                    port.apply_timeout()
+                        .map_err(|e| format!("Failed to set timeout: {}", e))?;
+                    return Ok(port);
+                }
+                Err(e) => last_error = format!("Failed to connect {}: {}", addr, e),
+            }
+        }
+        Err(last_error)
+    }
+
+    fn apply_timeout(&mut self) -> io::Result<()> {
+        // TcpStream 不接受 0 超时，最小按 1ms 处理
+        let timeout = self.timeout.max(Duration::from_millis(1));
+        self.stream.set_read_timeout(Some(timeout))?;
+        self.stream.set_write_timeout(Some(timeout.max(Duration::from_secs(1))))
+    }
+
+    // 非阻塞地读取/窥探当前可用数据；Unix 下按次使用 MSG_DONTWAIT，
+    // 不修改套接字的 O_NONBLOCK，避免影响 try_clone 出的句柄上正在阻塞读的监听线程
+    #[cfg(unix)]
+    fn recv_now(&self, buf: &mut [u8], peek: bool) -> io::Result<usize> {
+        use std::os::unix::io::AsRawFd;
+
+        let flags = libc::MSG_DONTWAIT | if peek { libc::MSG_PEEK } else { 0 };
+        let n = unsafe {
+            libc::recv(
+                self.stream.as_raw_fd(),
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+                flags,
+            )
+        };
+        if n < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(n as usize)
+        }
+    }
+
+    // 其他平台没有逐次的非阻塞标志，退回临时切换非阻塞模式
+    #[cfg(not(unix))]
+    fn recv_now(&self, buf: &mut [u8], peek: bool) -> io::Result<usize> {
+        self.stream.set_nonblocking(true)?;
+        let result = if peek {
+            self.stream.peek(buf)
+        } else {
+            (&self.stream).read(buf)
+        };
+        self.stream.set_nonblocking(false)?;
+        result
+    }
+}
+
+fn unsupported(what: &str) -> serialport::Error {
+    serialport::Error::new(
+        serialport::ErrorKind::Io(io::ErrorKind::Unsupported),
+        format!("{} is not supported on TCP ports", what),
+    )
+}
+
+impl Read for TcpSerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.stream.read(buf) {
+            // 对端关闭连接
+            Ok(0) if !buf.is_empty() => Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "TCP connection closed by peer",
+            )),
+            Ok(n) => Ok(n),
+            // 不同平台的读超时表现不同，统一映射为 TimedOut，与串口行为一致
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                Err(io::Error::new(io::ErrorKind::TimedOut, "Operation timed out"))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Write for TcpSerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl SerialPort for TcpSerialPort {
+    fn name(&self) -> Option<String> {
+        Some(self.name.clone())
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(self.baud_rate)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(self.data_bits)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(self.flow_control)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(self.parity)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(self.stop_bits)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        self.baud_rate = baud_rate;
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> serialport::Result<()> {
+        self.data_bits = data_bits;
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> serialport::Result<()> {
+        self.flow_control = flow_control;
+        Ok(())
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> serialport::Result<()> {
+        self.parity = parity;
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> serialport::Result<()> {
+        self.stop_bits = stop_bits;
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.timeout = timeout;
+        Ok(self.apply_timeout()?)
+    }
+
+    fn write_request_to_send(&mut self, _level: bool) -> serialport::Result<()> {
+        Err(unsupported("RTS control"))
+    }
+
+    fn write_data_terminal_ready(&mut self, _level: bool) -> serialport::Result<()> {
+        Err(unsupported("DTR control"))
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Err(unsupported("CTS status"))
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Err(unsupported("DSR status"))
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Err(unsupported("RI status"))
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Err(unsupported("CD status"))
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        let mut peek_buf = [0u8; 4096];
+        match self.recv_now(&mut peek_buf, true) {
+            // 非阻塞 peek 返回 0 表示对端已关闭
+            Ok(0) => Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "TCP connection closed by peer",
+            ).into()),
+            Ok(n) => Ok(n as u32),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        // 已交给内核协议栈的数据无法查询，视为已全部发出
+        Ok(0)
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        if matches!(buffer_to_clear, ClearBuffer::Output) {
+            return Ok(());
+        }
+        // 丢弃接收缓冲中的所有数据
+        let mut discard = [0u8; 4096];
+        loop {
+            match self.recv_now(&mut discard, false) {
+                Ok(0) => return Ok(()),
+                Ok(_) => continue,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(TcpSerialPort {
+            stream: self.stream.try_clone()?,
+            name: self.name.clone(),
+            timeout: self.timeout,
+            baud_rate: self.baud_rate,
+            data_bits: self.data_bits,
+            flow_control: self.flow_control,
+            parity: self.parity,
+            stop_bits: self.stop_bits,
+        }))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Err(unsupported("Break signal"))
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Err(unsupported("Break signal"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    // 本地回环连接：返回适配后的端口和服务端套接字
+    fn loopback() -> (TcpSerialPort, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let name = format!("{}{}", TCP_PREFIX, listener.local_addr().unwrap());
+        let port = TcpSerialPort::connect(&name, 9600, Duration::from_millis(50)).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        (port, server)
+    }
+
+    // 等待数据到达本端接收缓冲
+    fn wait_for_bytes(port: &TcpSerialPort, expected: u32) {
+        for _ in 0..100 {
+            if port.bytes_to_read().unwrap() >= expected {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        panic!("data did not arrive");
+    }
+
+    #[test]
+    fn bytes_to_read_peeks_without_consuming() {
+        let (mut port, mut server) = loopback();
+        assert_eq!(port.bytes_to_read().unwrap(), 0);
+
+        server.write_all(b"abc").unwrap();
+        wait_for_bytes(&port, 3);
+        assert_eq!(port.bytes_to_read().unwrap(), 3);
+
+        let mut buf = [0u8; 3];
+        port.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"abc");
+    }
+
+    #[test]
+    fn clear_discards_pending_input() {
+        let (mut port, mut server) = loopback();
+        server.write_all(&[0x55; 100]).unwrap();
+        wait_for_bytes(&port, 100);
+
+        port.clear(ClearBuffer::Input).unwrap();
+        assert_eq!(port.bytes_to_read().unwrap(), 0);
+
+        // 清空后套接字仍为阻塞模式，读操作按超时返回
+        let mut buf = [0u8; 1];
+        let err = port.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn bytes_to_read_reports_peer_close() {
+        let (port, server) = loopback();
+        drop(server);
+        let mut closed = false;
+        for _ in 0..100 {
+            if port.bytes_to_read().is_err() {
+                closed = true;
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(closed);
+    }
+}
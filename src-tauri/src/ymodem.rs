@@ -0,0 +1,493 @@
+// YMODEM 批量文件传输：块 0 携带文件名和大小，数据块 1024 字节 + CRC16/XMODEM
+// 批次结束时发送文件名为空的块 0
+use std::time::Duration;
+
+const SOH: u8 = 0x01;
+const STX: u8 = 0x02;
+const EOT: u8 = 0x04;
+const ACK: u8 = 0x06;
+const NAK: u8 = 0x15;
+const CAN: u8 = 0x18;
+// 接收端请求 CRC 模式
+const CRC_REQUEST: u8 = b'C';
+// 数据块末尾填充字节
+const PAD: u8 = 0x1A;
+
+const BLOCK_SIZE: usize = 128;
+const BLOCK_SIZE_1K: usize = 1024;
+// 接收缓冲最多预分配的字节数
+const MAX_PREALLOC: usize = 16 * 1024 * 1024;
+// 单个块的最大重试次数
+const MAX_RETRIES: u32 = 10;
+
+// 发送端等待接收端就绪的时间
+const READY_TIMEOUT: Duration = Duration::from_secs(60);
+// 等待 ACK/NAK 的时间
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+// 接收端等待块起始字节的时间，超时后重发请求
+const BLOCK_TIMEOUT: Duration = Duration::from_secs(3);
+// 块内字节间的最大间隔
+const BYTE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// 底层收发通道，由调用方适配到具体串口
+pub trait Link {
+    fn send(&mut self, data: &[u8]) -> Result<(), String>;
+    // 在超时前读取一个字节，超时返回 None
+    fn recv_byte(&mut self, timeout: Duration) -> Result<Option<u8>, String>;
+}
+
+// 传输进度：文件序号、文件名、已传输字节数、文件大小
+pub type ProgressFn<'a> = dyn FnMut(usize, &str, u64, u64) + 'a;
+
+// CRC16/XMODEM（多项式 0x1021，初值 0，高字节在前发送）
+pub fn crc16_xmodem(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            if crc & 0x8000 != 0 {
+                crc = (crc << 1) ^ 0x1021;
+            } else {
+                crc <<= 1;
+            }
+        }
+    }
+    crc
+}
+
+// 发送一批文件（文件名, 内容），全部结束后发送空块 0 结束批次
+pub fn send_files(
+    link: &mut dyn Link,
+    files: &[(String, Vec<u8>)],
+    on_progress: &mut ProgressFn,
+) -> Result<(), String> {
+    let result = send_batch(link, files, on_progress);
+    if result.is_err() {
+        abort(link);
+    }
+    result
+}
+
+fn send_batch(
+    link: &mut dyn Link,
+    files: &[(String, Vec<u8>)],
+    on_progress: &mut ProgressFn,
+) -> Result<(), String> {
+    for (index, (name, data)) in files.iter().enumerate() {
+        wait_for_crc_request(link)?;
+        send_block(link, 0, &header_block(name, data.len())?)?;
+        wait_for_crc_request(link)?;
+
+        let mut sent = 0usize;
+        let mut seq: u8 = 1;
+        on_progress(index, name, 0, data.len() as u64);
+        while sent < data.len() {
+            let remaining = data.len() - sent;
+            // 最后不足 128 字节时使用短块，减少填充
+            let size = if remaining > BLOCK_SIZE { BLOCK_SIZE_1K } else { BLOCK_SIZE };
+            let end = (sent + size).min(data.len());
+            let mut block = data[sent..end].to_vec();
+            block.resize(size, PAD);
+            send_block(link, seq, &block)?;
+            sent = end;
+            seq = seq.wrapping_add(1);
+            on_progress(index, name, sent as u64, data.len() as u64);
+        }
+        send_eot(link)?;
+    }
+
+    // 批次结束：文件名为空的块 0
+    wait_for_crc_request(link)?;
+    send_block(link, 0, &[0u8; BLOCK_SIZE])
+}
+
+// 块 0 内容：文件名\0 + 十进制大小，按需选择 128 或 1024 字节
+fn header_block(name: &str, size: usize) -> Result<Vec<u8>, String> {
+    if name.is_empty() {
+        return Err("File name must not be empty".to_string());
+    }
+    let mut block = name.as_bytes().to_vec();
+    block.push(0);
+    block.extend_from_slice(size.to_string().as_bytes());
+    block.push(0);
+    if block.len() > BLOCK_SIZE_1K {
+        return Err(format!("File name too long for YMODEM header: {}", name));
+    }
+    let block_size = if block.len() > BLOCK_SIZE { BLOCK_SIZE_1K } else { BLOCK_SIZE };
+    block.resize(block_size, 0);
+    Ok(block)
+}
+
+fn wait_for_crc_request(link: &mut dyn Link) -> Result<(), String> {
+    let deadline = std::time::Instant::now() + READY_TIMEOUT;
+    let mut cancels = 0;
+    while std::time::Instant::now() < deadline {
+        match link.recv_byte(BLOCK_TIMEOUT)? {
+            Some(CRC_REQUEST) => return Ok(()),
+            Some(CAN) => {
+                cancels += 1;
+                if cancels >= 2 {
+                    return Err("Transfer cancelled by receiver".to_string());
+                }
+            }
+            // 忽略接收端上一轮的残留字节
+            _ => cancels = 0,
+        }
+    }
+    Err("Timed out waiting for receiver to request CRC mode".to_string())
+}
+
+fn send_block(link: &mut dyn Link, seq: u8, data: &[u8]) -> Result<(), String> {
+    let mut packet = Vec::with_capacity(data.len() + 5);
+    packet.push(if data.len() == BLOCK_SIZE_1K { STX } else { SOH });
+    packet.push(seq);
+    packet.push(!seq);
+    packet.extend_from_slice(data);
+    packet.extend_from_slice(&crc16_xmodem(data).to_be_bytes());
+
+    for _ in 0..MAX_RETRIES {
+        link.send(&packet)?;
+        match link.recv_byte(REPLY_TIMEOUT)? {
+            Some(ACK) => return Ok(()),
+            Some(CAN) if link.recv_byte(BYTE_TIMEOUT)? == Some(CAN) => {
+                return Err("Transfer cancelled by receiver".to_string());
+            }
+            // NAK、超时或其它字节都重发
+            _ => {}
+        }
+    }
+    Err(format!("Block {} not acknowledged after {} attempts", seq, MAX_RETRIES))
+}
+
+// 发送 EOT：标准接收端先回 NAK，再次发送 EOT 后回 ACK；也兼容直接回 ACK 的实现
+fn send_eot(link: &mut dyn Link) -> Result<(), String> {
+    for _ in 0..MAX_RETRIES {
+        link.send(&[EOT])?;
+        if link.recv_byte(REPLY_TIMEOUT)? == Some(ACK) {
+            return Ok(());
+        }
+    }
+    Err("EOT not acknowledged".to_string())
+}
+
+fn abort(link: &mut dyn Link) {
+    let _ = link.send(&[CAN, CAN, CAN]);
+}
+
+// 收到的一个数据包
+enum Packet {
+    Block { seq: u8, data: Vec<u8> },
+    Eot,
+    Cancel,
+    // 超时、序号反码或 CRC 错误
+    Bad,
+}
+
+// 接收一批文件，每个文件接收完整后交给 on_file(文件名, 内容)
+// 文件名为空的块 0 表示批次结束
+pub fn receive_files(
+    link: &mut dyn Link,
+    on_file: &mut dyn FnMut(&str, &[u8]) -> Result<(), String>,
+    on_progress: &mut ProgressFn,
+) -> Result<usize, String> {
+    let result = receive_batch(link, on_file, on_progress);
+    if result.is_err() {
+        abort(link);
+    }
+    result
+}
+
+fn receive_batch(
+    link: &mut dyn Link,
+    on_file: &mut dyn FnMut(&str, &[u8]) -> Result<(), String>,
+    on_progress: &mut ProgressFn,
+) -> Result<usize, String> {
+    let mut index = 0;
+    loop {
+        let header = receive_header(link)?;
+        if header[0] == 0 {
+            link.send(&[ACK])?;
+            return Ok(index);
+        }
+        let (name, size) = parse_header(&header)?;
+        link.send(&[ACK])?;
+        link.send(&[CRC_REQUEST])?;
+
+        // 头部中的大小不可信，预分配设上限
+        let mut data = Vec::with_capacity(size.unwrap_or(0).min(MAX_PREALLOC));
+        let mut expected: u8 = 1;
+        let mut errors = 0;
+        let mut eot_seen = false;
+        on_progress(index, &name, 0, size.unwrap_or(0) as u64);
+        loop {
+            match read_packet(link)? {
+                Packet::Block { seq, data: block } if seq == expected => {
+                    data.extend_from_slice(&block);
+                    expected = expected.wrapping_add(1);
+                    errors = 0;
+                    link.send(&[ACK])?;
+                    let done = size.map_or(data.len(), |s| data.len().min(s));
+                    on_progress(index, &name, done as u64, size.unwrap_or(0) as u64);
+                }
+                // 发送端没收到 ACK 而重发的上一块
+                Packet::Block { seq, .. } if seq == expected.wrapping_sub(1) => {
+                    link.send(&[ACK])?;
+                }
+                Packet::Block { seq, .. } => {
+                    return Err(format!("Out of sequence block {} (expected {})", seq, expected));
+                }
+                Packet::Eot if eot_seen => {
+                    link.send(&[ACK])?;
+                    break;
+                }
+                Packet::Eot => {
+                    // 第一次 EOT 回 NAK 确认不是误码
+                    eot_seen = true;
+                    link.send(&[NAK])?;
+                }
+                Packet::Cancel => return Err("Transfer cancelled by sender".to_string()),
+                Packet::Bad => {
+                    errors += 1;
+                    if errors > MAX_RETRIES {
+                        return Err(format!("Too many errors receiving {}", name));
+                    }
+                    link.send(&[NAK])?;
+                }
+            }
+        }
+
+        // 有文件大小时按大小截断填充，否则去掉末尾的填充字节
+        match size {
+            Some(size) => data.truncate(size),
+            None => {
+                while data.last() == Some(&PAD) {
+                    data.pop();
+                }
+            }
+        }
+        on_file(&name, &data)?;
+        index += 1;
+    }
+}
+
+// 发送 'C' 请求并等待块 0
+fn receive_header(link: &mut dyn Link) -> Result<Vec<u8>, String> {
+    for _ in 0..MAX_RETRIES * 2 {
+        link.send(&[CRC_REQUEST])?;
+        match read_packet(link)? {
+            Packet::Block { seq: 0, data } => return Ok(data),
+            Packet::Cancel => return Err("Transfer cancelled by sender".to_string()),
+            // 上一个文件 EOT 的 ACK 丢失时发送端会重发 EOT
+            Packet::Eot => link.send(&[ACK])?,
+            _ => {}
+        }
+    }
+    Err("Timed out waiting for YMODEM header block".to_string())
+}
+
+// 解析块 0：文件名\0 十进制大小[ 其它字段]
+fn parse_header(block: &[u8]) -> Result<(String, Option<usize>), String> {
+    let name_end = block.iter().position(|&b| b == 0).unwrap_or(block.len());
+    let name = String::from_utf8_lossy(&block[..name_end]).into_owned();
+    let rest = block.get(name_end + 1..).unwrap_or(&[]);
+    let size_field: Vec<u8> = rest
+        .iter()
+        .take_while(|&&b| b != 0 && b != b' ')
+        .copied()
+        .collect();
+    let size = if size_field.is_empty() {
+        None
+    } else {
+        let text = String::from_utf8_lossy(&size_field);
+        Some(
+            text.parse::<usize>()
+                .map_err(|_| format!("Invalid file size in header: {}", text))?,
+        )
+    };
+    Ok((name, size))
+}
+
+fn read_packet(link: &mut dyn Link) -> Result<Packet, String> {
+    let size = match link.recv_byte(BLOCK_TIMEOUT)? {
+        Some(SOH) => BLOCK_SIZE,
+        Some(STX) => BLOCK_SIZE_1K,
+        Some(EOT) => return Ok(Packet::Eot),
+        Some(CAN) => {
+            if link.recv_byte(BYTE_TIMEOUT)? == Some(CAN) {
+                return Ok(Packet::Cancel);
+            }
+            return Ok(Packet::Bad);
+        }
+        _ => return Ok(Packet::Bad),
+    };
+
+    // 序号 + 反码 + 数据 + CRC
+    let mut packet = Vec::with_capacity(size + 4);
+    while packet.len() < size + 4 {
+        match link.recv_byte(BYTE_TIMEOUT)? {
+            Some(b) => packet.push(b),
+            None => return Ok(Packet::Bad),
+        }
+    }
+    let (seq, seq_complement) = (packet[0], packet[1]);
+    let data = &packet[2..size + 2];
+    let crc = u16::from_be_bytes([packet[size + 2], packet[size + 3]]);
+    if seq != !seq_complement || crc != crc16_xmodem(data) {
+        purge(link)?;
+        return Ok(Packet::Bad);
+    }
+    Ok(Packet::Block { seq, data: data.to_vec() })
+}
+
+// 丢弃线路上的残余字节，直到空闲
+fn purge(link: &mut dyn Link) -> Result<(), String> {
+    while link.recv_byte(BYTE_TIMEOUT)?.is_some() {}
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::sync::{Arc, Mutex};
+
+    // 内存中的一端：发送的每个包单独记录，可选地篡改指定序号的第一个数据块
+    struct MemoryLink {
+        tx: Sender<u8>,
+        rx: Receiver<u8>,
+        sent: Arc<Mutex<Vec<Vec<u8>>>>,
+        corrupt_seq: Option<u8>,
+    }
+
+    impl Link for MemoryLink {
+        fn send(&mut self, data: &[u8]) -> Result<(), String> {
+            self.sent.lock().unwrap().push(data.to_vec());
+            let mut data = data.to_vec();
+            if data.len() > 4 && matches!(data[0], SOH | STX) && Some(data[1]) == self.corrupt_seq {
+                data[3] ^= 0xFF;
+                self.corrupt_seq = None;
+            }
+            for byte in data {
+                self.tx.send(byte).map_err(|e| e.to_string())?;
+            }
+            Ok(())
+        }
+
+        fn recv_byte(&mut self, timeout: Duration) -> Result<Option<u8>, String> {
+            Ok(self.rx.recv_timeout(timeout).ok())
+        }
+    }
+
+    // 连接一对链路，返回（发送端, 接收端, 发送端发出的包）
+    fn link_pair(corrupt_seq: Option<u8>) -> (MemoryLink, MemoryLink, Arc<Mutex<Vec<Vec<u8>>>>) {
+        let (a_tx, b_rx) = channel();
+        let (b_tx, a_rx) = channel();
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let sender = MemoryLink { tx: a_tx, rx: a_rx, sent: sent.clone(), corrupt_seq };
+        let receiver = MemoryLink {
+            tx: b_tx,
+            rx: b_rx,
+            sent: Arc::new(Mutex::new(Vec::new())),
+            corrupt_seq: None,
+        };
+        (sender, receiver, sent)
+    }
+
+    // 在两个线程中完成一次批量传输，返回接收到的文件
+    fn transfer(
+        files: Vec<(String, Vec<u8>)>,
+        corrupt_seq: Option<u8>,
+    ) -> (Vec<(String, Vec<u8>)>, Vec<Vec<u8>>) {
+        let (mut sender, mut receiver, sent) = link_pair(corrupt_seq);
+        let send_thread = std::thread::spawn(move || {
+            send_files(&mut sender, &files, &mut |_, _, _, _| {})
+        });
+
+        let mut received = Vec::new();
+        let count = receive_files(
+            &mut receiver,
+            &mut |name, data| {
+                received.push((name.to_string(), data.to_vec()));
+                Ok(())
+            },
+            &mut |_, _, _, _| {},
+        )
+        .expect("receive failed");
+        send_thread.join().unwrap().expect("send failed");
+        assert_eq!(count, received.len());
+
+        let sent = sent.lock().unwrap().clone();
+        (received, sent)
+    }
+
+    fn pattern(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn transfers_multi_file_batch() {
+        let files = vec![
+            ("first.bin".to_string(), pattern(3000)),
+            ("second.txt".to_string(), b"hello".to_vec()),
+        ];
+        let (received, _) = transfer(files.clone(), None);
+        assert_eq!(received, files);
+    }
+
+    #[test]
+    fn short_last_block_is_padded_and_truncated() {
+        // 1024 + 100 字节：最后一块不足 128 字节，使用填充后的 128 字节块
+        let files = vec![("short.bin".to_string(), pattern(1124))];
+        let (received, sent) = transfer(files.clone(), None);
+        assert_eq!(received, files);
+        let last_data = sent
+            .iter()
+            .rfind(|p| p.len() == BLOCK_SIZE + 5 && p[1] == 2)
+            .expect("no short block sent");
+        assert_eq!(last_data[0], SOH);
+        assert!(last_data[3 + 100..3 + BLOCK_SIZE].iter().all(|&b| b == PAD));
+    }
+
+    #[test]
+    fn resends_eot_after_first_nak() {
+        let files = vec![
+            ("a".to_string(), pattern(10)),
+            ("b".to_string(), pattern(10)),
+        ];
+        let (received, sent) = transfer(files.clone(), None);
+        assert_eq!(received, files);
+        // 接收端第一次 EOT 回 NAK，每个文件都要发送两次 EOT
+        let eots = sent.iter().filter(|p| p.as_slice() == [EOT]).count();
+        assert_eq!(eots, 2 * files.len());
+    }
+
+    #[test]
+    fn retransmits_block_after_crc_mismatch() {
+        let files = vec![("crc.bin".to_string(), pattern(2048))];
+        let (received, sent) = transfer(files.clone(), Some(1));
+        assert_eq!(received, files);
+        let block_1_sends = sent
+            .iter()
+            .filter(|p| p.len() > 3 && matches!(p[0], SOH | STX) && p[1] == 1)
+            .count();
+        assert_eq!(block_1_sends, 2);
+    }
+
+    #[test]
+    fn empty_batch_sends_only_end_header() {
+        let (received, sent) = transfer(Vec::new(), None);
+        assert!(received.is_empty());
+        assert_eq!(sent.len(), 1);
+        let packet = &sent[0];
+        assert_eq!(&packet[..3], &[SOH, 0, 0xFF]);
+        assert!(packet[3..3 + BLOCK_SIZE].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn header_block_round_trips() {
+        let block = header_block("fw.bin", 1234).unwrap();
+        assert_eq!(block.len(), BLOCK_SIZE);
+        assert_eq!(parse_header(&block).unwrap(), ("fw.bin".to_string(), Some(1234)));
+        assert!(header_block("", 0).is_err());
+    }
+}